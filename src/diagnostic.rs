@@ -0,0 +1,208 @@
+use failure::Error;
+use graphql_parser::Pos;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt;
+
+/// A problem at a position in a file. Displayed like rustc does:
+///
+/// ```text
+/// error: unexpected `}`, expected `Name`, `:` or `)`
+///  --> queries/user.graphql:3:1
+///   |
+/// 3 | }
+///   | ^
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub path: String,
+    pub pos: Pos,
+    pub message: String,
+    /// The line `pos` is on, with tabs expanded the way the parser counts columns.
+    line: String,
+}
+
+impl Diagnostic {
+    pub fn new<T: Into<String>>(path: &str, source: &str, pos: Pos, message: T) -> Diagnostic {
+        let line = source
+            .lines()
+            .nth(pos.line.saturating_sub(1))
+            .unwrap_or("")
+            .replace('\u{feff}', "")
+            .replace('\t', "        ");
+
+        Diagnostic {
+            path: path.to_string(),
+            pos,
+            message: message.into(),
+            line,
+        }
+    }
+
+    /// Turn the error of parsing `source` into a diagnostic pointing at the offending token.
+    /// Other errors are returned unchanged.
+    pub fn from_parse_error(path: &str, source: &str, err: Error) -> Error {
+        match parse_error_message(&err.to_string()) {
+            Some((pos, message)) => Diagnostic::new(path, source, pos, message).into(),
+            None => err,
+        }
+    }
+
+    /// The diagnostic on a single line, for tools that read it.
+    pub fn short(&self) -> String {
+        format!("{}:{}: error: {}", self.path, self.pos, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line_number = self.pos.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let caret = " ".repeat(self.pos.column.saturating_sub(1));
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{}--> {}:{}", gutter, self.path, self.pos)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line_number, self.line.trim_end())?;
+        write!(f, "{} | {}^", gutter, caret)
+    }
+}
+
+/// Get the position and the message out of a graphql_parser error, which only has a
+/// `Display` like
+///
+/// ```text
+/// query parse error: Parse error at 3:1
+/// Unexpected `}[Punctuator]`
+/// Expected Name, : or )
+/// ```
+fn parse_error_message(error: &str) -> Option<(Pos, String)> {
+    lazy_static! {
+        static ref PARSE_ERROR: Regex =
+            Regex::new(r"(?s)^(?:query|schema) parse error: Parse error at (\d+):(\d+)\n(.*)")
+                .unwrap();
+        static ref TOKEN_KIND: Regex = Regex::new(r"\[[A-Za-z]+\]`").unwrap();
+    }
+
+    let captures = PARSE_ERROR.captures(error)?;
+    let pos = Pos {
+        line: captures[1].parse().ok()?,
+        column: captures[2].parse().ok()?,
+    };
+    let message = captures[3]
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let line = TOKEN_KIND.replace_all(line.trim(), "`");
+            let line = quote_expected(&line);
+            let mut chars = line.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some((pos, message))
+}
+
+/// Quote the tokens in a line like `Expected Name, : or )`, which the parser doesn't do.
+fn quote_expected(line: &str) -> String {
+    let quote = |item: &str| {
+        if item.starts_with('`') {
+            item.to_string()
+        } else {
+            format!("`{}`", item)
+        }
+    };
+
+    if !line.starts_with("Expected ") {
+        return line.to_string();
+    }
+    let items = &line["Expected ".len()..];
+    let (items, last) = match items.rfind(" or ") {
+        Some(or) => (&items[..or], Some(&items[or + " or ".len()..])),
+        None => (items, None),
+    };
+    let mut quoted = items.split(", ").map(quote).collect::<Vec<_>>().join(", ");
+    if let Some(last) = last {
+        quoted = format!("{} or {}", quoted, quote(last));
+    }
+    format!("Expected {}", quoted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::parse::{parse_query, parse_schema};
+
+    #[test]
+    fn query_parse_error() {
+        let source = "{\n  user(id: 1\n}";
+        let err = parse_query(source).unwrap_err().into();
+        let err = Diagnostic::from_parse_error("user.graphql", source, err);
+
+        assert_eq!(
+            err.to_string(),
+            "
+error: unexpected `}`, expected `Name`, `:` or `)`
+ --> user.graphql:3:1
+  |
+3 | }
+  | ^
+            "
+            .trim()
+        );
+    }
+
+    #[test]
+    fn schema_parse_error() {
+        let source = "type User {\n\tid: \n}";
+        let err = parse_schema(source).unwrap_err().into();
+        let err = Diagnostic::from_parse_error("schema.graphql", source, err);
+        let diagnostic = err.downcast::<Diagnostic>().unwrap();
+
+        assert_eq!(diagnostic.pos, Pos { line: 3, column: 1 });
+        assert_eq!(diagnostic.message, "unexpected `}`, expected `Name` or `[`");
+    }
+
+    #[test]
+    fn caret_under_token() {
+        let diagnostic = Diagnostic::new(
+            "a.graphql",
+            "query {\n\ta(b: )\n}",
+            Pos {
+                line: 2,
+                column: 14,
+            },
+            "unexpected `)`",
+        );
+
+        assert!(diagnostic
+            .to_string()
+            .ends_with("2 |         a(b: )\n  |              ^"));
+    }
+
+    #[test]
+    fn short() {
+        let diagnostic = Diagnostic::new(
+            "a.graphql",
+            "{ a( }",
+            Pos { line: 1, column: 6 },
+            "unexpected `}`",
+        );
+
+        assert_eq!(diagnostic.short(), "a.graphql:1:6: error: unexpected `}`");
+    }
+
+    #[test]
+    fn other_errors_unchanged() {
+        let err = failure::format_err!("something else");
+        let err = Diagnostic::from_parse_error("a.graphql", "", err);
+
+        assert_eq!(err.to_string(), "something else");
+    }
+}
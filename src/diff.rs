@@ -26,6 +26,31 @@ impl Mismatch {
             lines: Vec::new(),
         }
     }
+
+    /// The `@@ -start,count +start,count @@` line in front of the hunk, with the lines it
+    /// covers in the original and in the changed file.
+    fn header(&self) -> String {
+        let count = |skipped: fn(&DiffLine) -> bool| {
+            self.lines.iter().filter(|line| !skipped(line)).count() as u32
+        };
+        let orig = count(|line| matches!(line, DiffLine::Expected(_)));
+        let changed = count(|line| matches!(line, DiffLine::Resulting(_)));
+
+        format!(
+            "@@ -{} +{} @@",
+            hunk_range(self.line_number_orig, orig),
+            hunk_range(self.line_number, changed)
+        )
+    }
+}
+
+/// An empty range is given by the line before it, as `diff -u` does.
+fn hunk_range(start: u32, count: u32) -> String {
+    if count == 0 {
+        format!("{},0", start.saturating_sub(1))
+    } else {
+        format!("{},{}", start, count)
+    }
 }
 
 pub struct OutputWriter {
@@ -33,12 +58,15 @@ pub struct OutputWriter {
 }
 
 impl OutputWriter {
+    /// A writer that only colors the output if it goes to a terminal, so that diffs written to
+    /// a file can be applied.
     pub fn new() -> Self {
-        if let Some(t) = term::stdout() {
-            return OutputWriter { terminal: Some(t) };
-        } else {
-            return OutputWriter { terminal: None };
+        if atty::is(atty::Stream::Stdout) {
+            if let Some(t) = term::stdout() {
+                return OutputWriter { terminal: Some(t) };
+            }
         }
+        OutputWriter { terminal: None }
     }
 
     pub fn writeln(&mut self, msg: &str, color: Option<term::color::Color>) {
@@ -57,18 +85,27 @@ impl OutputWriter {
     }
 }
 
+/// The hunks of changes between `expected` and `actual`, with up to `context_size` unchanged
+/// lines around each change. The lines keep their line endings, so that a missing newline at
+/// the end of a file shows up as a change.
 pub fn make_diff(expected: &str, actual: &str, context_size: usize) -> Vec<Mismatch> {
     let mut line_number = 1;
     let mut line_number_orig = 1;
     let mut context_queue: VecDeque<&str> = VecDeque::with_capacity(context_size);
-    let mut lines_since_mismatch = context_size + 1;
+    // Changes with no more than twice the context between them go in the same hunk, like with
+    // `diff -u`, as none of the unchanged lines between them are left out.
+    let max_gap = 2 * context_size;
+    let mut lines_since_mismatch = max_gap + 1;
     let mut results = Vec::new();
     let mut mismatch = Mismatch::new(0, 0);
 
-    for result in diff::lines(expected, actual) {
+    let expected = expected.split_inclusive('\n').collect::<Vec<_>>();
+    let actual = actual.split_inclusive('\n').collect::<Vec<_>>();
+
+    for result in diff::slice(&expected, &actual) {
         match result {
-            diff::Result::Left(str) => {
-                if lines_since_mismatch >= context_size && lines_since_mismatch > 0 {
+            diff::Result::Left(&str) => {
+                if lines_since_mismatch > max_gap {
                     results.push(mismatch);
                     mismatch = Mismatch::new(
                         line_number - context_queue.len() as u32,
@@ -84,8 +121,8 @@ pub fn make_diff(expected: &str, actual: &str, context_size: usize) -> Vec<Misma
                 line_number_orig += 1;
                 lines_since_mismatch = 0;
             }
-            diff::Result::Right(str) => {
-                if lines_since_mismatch >= context_size && lines_since_mismatch > 0 {
+            diff::Result::Right(&str) => {
+                if lines_since_mismatch > max_gap {
                     results.push(mismatch);
                     mismatch = Mismatch::new(
                         line_number - context_queue.len() as u32,
@@ -101,7 +138,7 @@ pub fn make_diff(expected: &str, actual: &str, context_size: usize) -> Vec<Misma
                 line_number += 1;
                 lines_since_mismatch = 0;
             }
-            diff::Result::Both(str, _) => {
+            diff::Result::Both(&str, _) => {
                 if context_queue.len() >= context_size {
                     let _ = context_queue.pop_front();
                 }
@@ -125,26 +162,114 @@ pub fn make_diff(expected: &str, actual: &str, context_size: usize) -> Vec<Misma
     results
 }
 
-pub fn print_diff(diff: Vec<Mismatch>) {
-    let line_terminator = "";
-
-    let mut writer = OutputWriter::new();
+/// The lines of a unified diff of the file at `path`, which `patch -p1` and `git apply` can
+/// apply.
+pub fn unified_diff(path: &str, diff: &[Mismatch]) -> Vec<String> {
+    let mut lines = vec![format!("--- a/{}", path), format!("+++ b/{}", path)];
 
     for mismatch in diff {
-        for line in mismatch.lines {
-            match line {
-                DiffLine::Context(ref str) => {
-                    writer.writeln(&format!(" {}{}", str, line_terminator), None)
+        lines.push(mismatch.header());
+        for line in &mismatch.lines {
+            let (prefix, str) = match line {
+                DiffLine::Context(str) => (' ', str),
+                DiffLine::Expected(str) => ('+', str),
+                DiffLine::Resulting(str) => ('-', str),
+            };
+            match str.strip_suffix('\n') {
+                Some(str) => lines.push(format!("{}{}", prefix, str)),
+                None => {
+                    lines.push(format!("{}{}", prefix, str));
+                    lines.push("\\ No newline at end of file".to_string());
                 }
-                DiffLine::Expected(ref str) => writer.writeln(
-                    &format!("+{}{}", str, line_terminator),
-                    Some(term::color::GREEN),
-                ),
-                DiffLine::Resulting(ref str) => writer.writeln(
-                    &format!("-{}{}", str, line_terminator),
-                    Some(term::color::RED),
-                ),
             }
         }
     }
+
+    lines
+}
+
+pub fn print_diff(path: &str, diff: Vec<Mismatch>) {
+    let mut writer = OutputWriter::new();
+
+    // The first two lines are the file headers.
+    for (i, line) in unified_diff(path, &diff).into_iter().enumerate() {
+        let color = if i < 2 {
+            None
+        } else if line.starts_with("@@") {
+            Some(term::color::CYAN)
+        } else if line.starts_with('+') {
+            Some(term::color::GREEN)
+        } else if line.starts_with('-') {
+            Some(term::color::RED)
+        } else {
+            None
+        };
+        writer.writeln(&line, color);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unified(expected: &str, actual: &str, context_size: usize) -> Vec<String> {
+        unified_diff("a.graphql", &make_diff(expected, actual, context_size))[2..].to_vec()
+    }
+
+    #[test]
+    fn file_headers() {
+        let diff = unified_diff("queries/a.graphql", &make_diff("a\n", "b\n", 3));
+
+        assert_eq!(
+            diff[..2],
+            ["--- a/queries/a.graphql", "+++ b/queries/a.graphql"]
+        );
+    }
+
+    #[test]
+    fn hunks_with_context() {
+        let original = "a\nb\nc\nd\ne\nf\ng\n";
+        let changed = "a\nB\nc\nd\ne\nf\nG\nh\n";
+
+        assert_eq!(
+            unified(original, changed, 1),
+            vec![
+                "@@ -1,3 +1,3 @@",
+                " a",
+                "-b",
+                "+B",
+                " c",
+                "@@ -6,2 +6,3 @@",
+                " f",
+                "-g",
+                "+G",
+                "+h",
+            ]
+        );
+        assert_eq!(unified(original, changed, 2)[0], "@@ -1,7 +1,8 @@");
+        assert!(make_diff(original, original, 3).is_empty());
+    }
+
+    #[test]
+    fn empty_ranges() {
+        assert_eq!(
+            unified("a\nc\n", "a\nb\nc\n", 0),
+            vec!["@@ -1,0 +2,1 @@", "+b"]
+        );
+        assert_eq!(unified("a\n", "", 3), vec!["@@ -1,1 +0,0 @@", "-a"]);
+    }
+
+    #[test]
+    fn missing_newline_at_end() {
+        assert_eq!(
+            unified("a\nb", "a\nb\n", 3),
+            vec![
+                "@@ -1,2 +1,2 @@",
+                " a",
+                "-b",
+                "\\ No newline at end of file",
+                "+b",
+            ]
+        );
+    }
 }
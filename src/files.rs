@@ -0,0 +1,156 @@
+use failure::{bail, Error};
+use glob::{MatchOptions, Pattern};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+const EXTENSIONS: &[&str] = &["graphql", "gql"];
+
+/// Expand the paths given on the command line into the files they name. Directories are
+/// searched for `.graphql` and `.gql` files, and glob patterns such as `"queries/**/*.graphql"`
+/// are matched against the files below their first directory without wildcards.
+///
+/// Files found by searching are skipped if they are hidden or ignored by a `.gitignore` file.
+/// Files that are named explicitly never are.
+pub fn expand(paths: &[String]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if is_pattern(path) {
+            let normalized = normalize_pattern(path);
+            let pattern = Pattern::new(&normalized)?;
+            let options = MatchOptions {
+                require_literal_separator: true,
+                ..MatchOptions::new()
+            };
+
+            let matches = walk(&pattern_base(&normalized))
+                .filter(|file| pattern.matches_path_with(file, &options))
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                bail!("no files match \"{}\"", path);
+            }
+            files.extend(matches);
+        } else if Path::new(path).is_dir() {
+            files.extend(walk(Path::new(path)).filter(|file| has_graphql_extension(file)));
+        } else {
+            files.push(PathBuf::from(path));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+fn is_pattern(path: &str) -> bool {
+    path.contains(&['*', '?', '['][..])
+}
+
+/// The pattern without `.` components, like a leading `./`, which the paths of the walked files
+/// don't have either.
+fn normalize_pattern(pattern: &str) -> String {
+    Path::new(pattern)
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The directory a pattern can match files in, made of the components before the first one
+/// with a wildcard.
+fn pattern_base(pattern: &str) -> PathBuf {
+    let base = Path::new(pattern)
+        .components()
+        .take_while(|component| !is_pattern(&component.as_os_str().to_string_lossy()))
+        .collect::<PathBuf>();
+
+    if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    }
+}
+
+/// The files below `dir` in sorted order, without the `./` the walk puts in front of them for
+/// the current directory.
+fn walk(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    let mut files = WalkBuilder::new(dir)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| match entry.path().strip_prefix(".") {
+            Ok(path) => path.to_path_buf(),
+            Err(_) => entry.into_path(),
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files.into_iter()
+}
+
+fn has_graphql_extension(file: &Path) -> bool {
+    match file.extension() {
+        Some(ext) => EXTENSIONS.iter().any(|known| ext == *known),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn base_of_pattern() {
+        assert_eq!(pattern_base("**/*.graphql"), PathBuf::from("."));
+        assert_eq!(
+            pattern_base("src/queries/*/user.graphql"),
+            PathBuf::from("src/queries")
+        );
+    }
+
+    #[test]
+    fn normalized_patterns() {
+        assert_eq!(
+            normalize_pattern("./queries/*.graphql"),
+            "queries/*.graphql"
+        );
+        assert_eq!(normalize_pattern("./*.graphql"), "*.graphql");
+        assert_eq!(normalize_pattern("src/./**/*.gql"), "src/**/*.gql");
+    }
+
+    #[test]
+    fn expand_directories_and_patterns() {
+        let dir = std::env::temp_dir().join(format!("gqltools-files-{}", std::process::id()));
+        for file in &[
+            "queries/user.graphql",
+            "queries/team/team.gql",
+            "queries/notes.txt",
+            "queries/.hidden/old.graphql",
+            "schema.graphql",
+        ] {
+            let file = dir.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "").unwrap();
+        }
+
+        let path = |path: &str| dir.join(path).to_string_lossy().into_owned();
+        let in_dir = expand(&[path("queries")]);
+        let matching = expand(&[path("*.graphql"), path("schema.graphql")]);
+        let with_dot = expand(&[path("./queries/*.graphql")]);
+        let nothing_matching = expand(&[path("*.gql")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            in_dir.unwrap(),
+            vec![
+                dir.join("queries/team/team.gql"),
+                dir.join("queries/user.graphql"),
+            ]
+        );
+        assert_eq!(matching.unwrap(), vec![dir.join("schema.graphql")]);
+        assert_eq!(with_dot.unwrap(), vec![dir.join("queries/user.graphql")]);
+        assert!(nothing_matching.is_err());
+    }
+}
@@ -1,11 +1,19 @@
+use self::comments::{Comments, Positions};
+use self::config::Config;
+use self::doc::*;
+use crate::ast::query::{Directive, Value};
 use failure::Error;
+use graphql_parser::Pos;
 use std::fmt;
 
-const MAX_LINE_LENGTH: usize = 80;
-const INDENT_SIZE: usize = 2;
-
+pub mod comments;
+pub mod config;
+pub mod doc;
+pub mod minify;
+pub mod parse;
 pub mod query;
 pub mod schema;
+pub mod verify;
 
 #[derive(Clone)]
 pub struct Indentation {
@@ -52,12 +60,15 @@ impl Output {
         self.buf.push_str(s.as_ref());
     }
 
-    fn current_line(&self) -> &str {
-        self.buf.lines().last().unwrap_or("")
+    /// Remove the spaces at the end of the current line.
+    fn trim_line_end(&mut self) {
+        let end = self.buf.trim_end_matches(' ').len();
+        self.buf.truncate(end);
     }
 
-    fn current_line_length(&self) -> usize {
-        self.current_line().len()
+    #[cfg(test)]
+    fn current_line(&self) -> &str {
+        self.buf.lines().last().unwrap_or("")
     }
 
     fn trim(&self) -> &str {
@@ -71,31 +82,260 @@ impl fmt::Display for Output {
     }
 }
 
-pub fn map_join<I, T, K, F>(
-    iter: I,
-    mapper: F,
-    sep: &str,
-    out: &mut Output,
-) where
-    I: Iterator<Item = T>,
-    F: Fn(T) -> K,
-    T: std::fmt::Display,
-    K: std::fmt::Display,
-{
-    let joined = iter
-        .map(|thing| format!("{}", mapper(thing)))
-        .collect::<Vec<_>>()
-        .join(sep);
-    out.push_str(&joined);
+/// The settings and the comments of the document being formatted.
+pub struct Context {
+    config: Config,
+    comments: Comments,
+    positions: Positions,
+}
+
+impl Context {
+    fn new(config: &Config, comments: Comments, positions: Positions) -> Context {
+        Context {
+            config: config.clone(),
+            comments,
+            positions,
+        }
+    }
+
+    /// The positions of the `count` items of the node at `pos` that have none in the AST, like
+    /// its arguments. Items that weren't found in the source get the default position, which
+    /// has no comments.
+    fn positions(&self, pos: Pos, count: usize) -> Vec<Pos> {
+        let mut positions = self.positions.get(&pos).cloned().unwrap_or_default();
+        positions.resize(count, Pos::default());
+        positions
+    }
+
+    /// Put the comments of the node at `pos` around its doc: the leading comments on their own
+    /// lines in front of it, and the trailing comment at the end of its first line.
+    fn with_comments(&mut self, pos: Pos, doc: Doc) -> Doc {
+        self.with_description(pos, nil(), doc)
+    }
+
+    /// Like `with_comments`, for a node with a description in front of it. The trailing comment
+    /// goes at the end of the first line after the description, as a comment can't be put
+    /// inside a string.
+    fn with_description(&mut self, pos: Pos, description: Doc, doc: Doc) -> Doc {
+        let mut docs = Vec::new();
+        for comment in self.comments.leading(pos) {
+            docs.push(text(comment));
+            docs.push(hardline());
+        }
+        docs.push(description);
+        if let Some(comment) = self.comments.trailing(pos) {
+            docs.push(line_suffix(format!(" {}", comment)));
+            docs.push(break_parent());
+        }
+        docs.push(doc);
+        concat(docs)
+    }
+
+    /// The comments at the end of the block of the node at `pos`, each on its own line.
+    fn dangling_comments(&mut self, pos: Pos) -> Doc {
+        let comments = self.comments.dangling(pos);
+        concat(
+            comments
+                .into_iter()
+                .map(|comment| concat(vec![hardline(), text(comment)]))
+                .collect(),
+        )
+    }
+
+    /// The comments after the last node of the document, after a blank line.
+    fn remaining_comments(&mut self) -> Doc {
+        let comments = self.comments.remaining();
+        if comments.is_empty() {
+            return nil();
+        }
+        concat(vec![
+            hardline(),
+            hardline(),
+            join(comments.into_iter().map(text).collect(), hardline()),
+        ])
+    }
+}
+
+/// The body of a node, like a selection set or the fields of a type, with one item per line.
+fn block(items: Vec<Doc>, separator: Doc, dangling_comments: Doc) -> Doc {
+    concat(vec![
+        text("{"),
+        nest(concat(vec![
+            hardline(),
+            join(items, separator),
+            dangling_comments,
+        ])),
+        hardline(),
+        text("}"),
+    ])
+}
+
+fn blank_line() -> Doc {
+    concat(vec![hardline(), hardline()])
+}
+
+/// A list between brackets, like arguments or a list value. The items go on separate lines if
+/// they don't fit on the current line, or if one of them spans multiple lines. Dangling
+/// comments go after the items, before the closing bracket.
+fn list(open: &str, items: Vec<Doc>, dangling_comments: Doc, close: &str, cx: &Context) -> Doc {
+    if items.is_empty() {
+        return text(format!("{}{}", open, close));
+    }
+
+    let trailing_comma = if cx.config.trailing_commas {
+        if_break(text(","), nil())
+    } else {
+        nil()
+    };
+
+    group(concat(vec![
+        text(open),
+        nest(concat(vec![
+            softline(),
+            join(items, concat(vec![text(","), line()])),
+            trailing_comma,
+            dangling_comments,
+        ])),
+        softline(),
+        text(close),
+    ]))
+}
+
+/// A parenthesized list of named arguments, sorted by name unless configured otherwise.
+fn argument_list(mut args: Vec<(String, Doc)>, dangling_comments: Doc, cx: &Context) -> Doc {
+    if cx.config.sort.arguments {
+        args.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    let args = args.into_iter().map(|(_, arg)| arg).collect();
+    list("(", args, dangling_comments, ")", cx)
+}
+
+/// The arguments of a field or directive in a query, or of a directive in a schema.
+fn format_arguments(args: &[(String, Value)], cx: &Context) -> Doc {
+    let args = args
+        .iter()
+        .map(|(name, value)| {
+            let arg = concat(vec![text(format!("{}: ", name)), format_value(value, cx)]);
+            (name.clone(), arg)
+        })
+        .collect();
+    argument_list(args, nil(), cx)
+}
+
+/// A value. Objects and lists break over multiple lines if they don't fit on the current
+/// line.
+fn format_value(value: &Value, cx: &Context) -> Doc {
+    match value {
+        Value::Object(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| {
+                    concat(vec![text(format!("{}: ", name)), format_value(value, cx)])
+                })
+                .collect();
+            list("{", fields, nil(), "}", cx)
+        }
+        Value::List(items) => {
+            let items = items.iter().map(|item| format_value(item, cx)).collect();
+            list("[", items, nil(), "]", cx)
+        }
+        Value::String(s) => string_value(s),
+        Value::Float(f) => text(float_value(*f)),
+        _ => text(value.to_string()),
+    }
+}
+
+/// A float that parses as a float again. graphql_parser prints `1.0` as `1`, an int.
+fn float_value(f: f64) -> String {
+    format!("{:?}", f)
+}
+
+/// A string value, as a block string if it has multiple lines and a block string has the same
+/// value.
+fn string_value(s: &str) -> Doc {
+    if s.contains('\n') && is_block_string_safe(s) {
+        block_string(s)
+    } else {
+        quoted_string(s)
+    }
+}
+
+fn quoted_string(s: &str) -> Doc {
+    text(format!("\"{}\"", escape_string(s)))
+}
+
+/// A block string with the quotes on lines of their own.
+fn block_string(s: &str) -> Doc {
+    let mut docs = vec![text("\"\"\""), literal_line()];
+    for line in s.lines() {
+        if !line.is_empty() {
+            docs.push(text(line.replace("\"\"\"", "\\\"\"\"")));
+        }
+        docs.push(literal_line());
+    }
+    docs.push(text("\"\"\""));
+    concat(docs)
+}
+
+/// Whether a block string holding `s` on the lines between the quotes has the value `s`.
+/// Block strings lose the indentation common to all lines, blank lines at the start and end,
+/// and carriage returns. The formatter removes whitespace at the end of lines.
+fn is_block_string_safe(s: &str) -> bool {
+    let no_control_chars = s.chars().all(|c| c == '\n' || c == '\t' || !c.is_control());
+    let lines = s.lines().map(str::trim_end).collect::<Vec<_>>();
+
+    no_control_chars && parse::block_string_value(&format!("\n{}\n", lines.join("\n"))) == s
+}
+
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_directives(directives: &[Directive], cx: &Context) -> Doc {
+    let directives = directives
+        .iter()
+        .map(|directive| concat(vec![text(" "), format_directive(directive, cx)]))
+        .collect();
+    concat(directives)
+}
+
+/// A directive without the space that `format_directives` puts in front of it.
+fn format_directive(directive: &Directive, cx: &Context) -> Doc {
+    let name = text(format!("@{}", directive.name));
+    if directive.arguments.is_empty() {
+        name
+    } else {
+        concat(vec![name, format_arguments(&directive.arguments, cx)])
+    }
 }
 
 #[cfg(test)]
 pub fn format_test<F>(formatter: F, query: &str, expected: &str)
 where
-    F: Fn(&str) -> Result<String, Error>,
+    F: Fn(&str, &Config) -> Result<String, Error>,
+{
+    format_test_with_config(formatter, &Config::default(), query, expected);
+}
+
+#[cfg(test)]
+pub fn format_test_with_config<F>(formatter: F, config: &Config, query: &str, expected: &str)
+where
+    F: Fn(&str, &Config) -> Result<String, Error>,
 {
     let query = query.trim();
-    let actual = formatter(query).unwrap();
+    let actual = formatter(query, config).unwrap();
 
     let expected = expected.trim();
 
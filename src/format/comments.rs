@@ -0,0 +1,487 @@
+//! The graphql_parser AST doesn't keep `#` comments, so they are lexed separately and
+//! attached to the AST nodes around them by source position. The formatters then write each
+//! comment next to its node, wherever sorting moves the node.
+//!
+//! Some nodes, like the arguments of a field in a query or the members of a union, have no
+//! positions in the AST. Their positions are found in the tokens of the source instead.
+
+use graphql_parser::Pos;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comment {
+    pos: Pos,
+    text: String,
+    /// Whether the comment follows other tokens on its line.
+    trailing: bool,
+}
+
+/// The braces around the body of a node, like a selection set or the fields of a type.
+#[derive(Debug, Clone, Copy)]
+pub struct Block {
+    /// The position of the node the dangling comments of the block are attached to.
+    pub owner: Pos,
+    pub start: Pos,
+    pub end: Pos,
+}
+
+/// The positions of the nodes that have none in the AST, like the arguments of a field, by the
+/// position of the node they belong to.
+pub type Positions = HashMap<Pos, Vec<Pos>>;
+
+/// The comments, brackets and other tokens of a source text.
+pub struct Trivia {
+    comments: Vec<Comment>,
+    brackets: Vec<(Pos, char)>,
+    /// The names and punctuation outside of strings and comments.
+    tokens: Vec<(Pos, String)>,
+}
+
+impl Trivia {
+    pub fn lex(source: &str) -> Trivia {
+        let mut comments = Vec::new();
+        let mut brackets = Vec::new();
+        let mut tokens = Vec::new();
+
+        let mut chars = source.chars().peekable();
+        let mut pos = Pos { line: 1, column: 1 };
+        let mut code_on_line = false;
+
+        while let Some(c) = chars.next() {
+            let start = pos;
+            advance(&mut pos, c);
+
+            match c {
+                '\n' => code_on_line = false,
+                ' ' | '\t' | '\r' | ',' | '\u{feff}' => {}
+                '#' => {
+                    let mut text = String::from("#");
+                    while let Some(&c) = chars.peek() {
+                        if c == '\n' || c == '\r' {
+                            break;
+                        }
+                        text.push(c);
+                        advance(&mut pos, c);
+                        chars.next();
+                    }
+                    comments.push(Comment {
+                        pos: start,
+                        text: text.trim_end().to_string(),
+                        trailing: code_on_line,
+                    });
+                }
+                '"' => {
+                    code_on_line = true;
+                    let block = chars.peek() == Some(&'"') && {
+                        let mut lookahead = chars.clone();
+                        lookahead.next();
+                        lookahead.next() == Some('"')
+                    };
+
+                    if block {
+                        for _ in 0..2 {
+                            advance(&mut pos, chars.next().unwrap());
+                        }
+                        skip_block_string(&mut chars, &mut pos);
+                    } else {
+                        skip_string(&mut chars, &mut pos);
+                    }
+                }
+                '{' | '}' | '(' | ')' => {
+                    code_on_line = true;
+                    brackets.push((start, c));
+                    tokens.push((start, c.to_string()));
+                }
+                '_' | 'a'..='z' | 'A'..='Z' => {
+                    code_on_line = true;
+                    let mut name = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c != '_' && !c.is_ascii_alphanumeric() {
+                            break;
+                        }
+                        name.push(c);
+                        advance(&mut pos, c);
+                        chars.next();
+                    }
+                    tokens.push((start, name));
+                }
+                '[' | ']' | ':' | '=' | '|' => {
+                    code_on_line = true;
+                    tokens.push((start, c.to_string()));
+                }
+                _ => code_on_line = true,
+            }
+        }
+
+        Trivia {
+            comments,
+            brackets,
+            tokens,
+        }
+    }
+
+    /// The positions of the keys, the names followed by `:`, directly inside the brackets
+    /// opening at `open`, like the arguments of a field.
+    pub fn keys(&self, open: Pos) -> Vec<(Pos, &str)> {
+        let mut tokens = self
+            .tokens
+            .iter()
+            .skip_while(|(pos, _)| *pos < open)
+            .peekable();
+        let mut depth = 0;
+        let mut keys = Vec::new();
+
+        while let Some((pos, token)) = tokens.next() {
+            match token.as_str() {
+                "(" | "{" | "[" => depth += 1,
+                ")" | "}" | "]" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ if depth == 1 && tokens.peek().is_some_and(|(_, next)| next == ":") => {
+                    keys.push((*pos, token.as_str()))
+                }
+                _ => {}
+            }
+        }
+
+        keys
+    }
+
+    /// The parentheses after `pos`, around the arguments of a field that has some. The
+    /// dangling comments of the arguments are attached to `pos`.
+    pub fn arguments_after(&self, pos: Pos) -> Option<Block> {
+        let mut brackets = self
+            .brackets
+            .iter()
+            .skip_while(|&&(bracket_pos, bracket)| bracket_pos < pos || bracket != '(');
+        let start = brackets.next()?.0;
+        let mut parens = 1;
+
+        for &(bracket_pos, bracket) in brackets {
+            match bracket {
+                '(' => parens += 1,
+                ')' => {
+                    parens -= 1;
+                    if parens == 0 {
+                        return Some(Block {
+                            owner: pos,
+                            start,
+                            end: bracket_pos,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// The positions of the members of the union at `pos`, which follow the first `=` after
+    /// it in the order of `names`.
+    pub fn union_members<T: AsRef<str>>(&self, pos: Pos, names: &[T]) -> Vec<Pos> {
+        let mut tokens = self
+            .tokens
+            .iter()
+            .skip_while(|(token_pos, token)| *token_pos <= pos || token != "=");
+
+        names
+            .iter()
+            .filter_map(|name| {
+                tokens
+                    .find(|(_, token)| token == name.as_ref())
+                    .map(|(pos, _)| *pos)
+            })
+            .collect()
+    }
+
+    /// The body of the definition at `pos`. Braces inside parentheses, like object values in
+    /// directive arguments, are skipped.
+    pub fn body_after(&self, pos: Pos) -> Option<Block> {
+        let mut parens = 0;
+        let mut start = None;
+        let mut braces = 0;
+
+        for &(bracket_pos, bracket) in self.brackets.iter().filter(|(p, _)| *p > pos) {
+            match (bracket, start) {
+                ('(', _) => parens += 1,
+                (')', _) => parens -= 1,
+                ('{', None) if parens == 0 => {
+                    start = Some(bracket_pos);
+                    braces = 1;
+                }
+                ('{', Some(_)) => braces += 1,
+                ('}', Some(start)) => {
+                    braces -= 1;
+                    if braces == 0 {
+                        return Some(Block {
+                            owner: pos,
+                            start,
+                            end: bracket_pos,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Attach every comment to a node. A comment following a node on the same line trails
+    /// the first node starting on that line, as the nodes after it on the line, like its
+    /// arguments, may be formatted on the same line again. Other comments lead the next node
+    /// in their block, or dangle at the end of the block if there is none.
+    ///
+    /// Comments between the parentheses of arguments go with the arguments, so that comments
+    /// inside values stay between the parentheses. Only a comment right after the opening
+    /// parenthesis trails the field, which is where the field's trailing comment is printed
+    /// when its arguments break.
+    ///
+    /// `anchors` are the positions of the nodes, `blocks` the bodies of the nodes that have
+    /// one, and their arguments.
+    pub fn attach(self, anchors: &[Pos], blocks: &[Block]) -> Comments {
+        let mut anchors = anchors.to_vec();
+        anchors.sort_unstable();
+        anchors.dedup();
+
+        let mut comments = Comments::default();
+        let brackets = self.brackets;
+
+        for comment in self.comments {
+            let block = blocks
+                .iter()
+                .filter(|block| block.start < comment.pos && comment.pos < block.end)
+                .max_by_key(|block| block.start);
+            let arguments = block.filter(|block| brackets.contains(&(block.start, '(')));
+
+            if comment.trailing {
+                let mut on_line = anchors
+                    .iter()
+                    .filter(|pos| pos.line == comment.pos.line && **pos < comment.pos);
+                let on_line = match arguments {
+                    Some(arguments) => on_line
+                        .clone()
+                        .find(|pos| **pos > arguments.start)
+                        .or_else(|| on_line.next()),
+                    None => on_line.next(),
+                };
+
+                if let Some(pos) = on_line {
+                    let text = match comments.trailing.remove(pos) {
+                        Some(text) => format!("{} {}", text, comment.text),
+                        None => comment.text,
+                    };
+                    comments.trailing.insert(*pos, text);
+                    continue;
+                }
+            }
+
+            let next = anchors.iter().find(|pos| **pos > comment.pos);
+
+            match (block, next) {
+                (Some(block), Some(next)) if *next < block.end => comments
+                    .leading
+                    .entry(*next)
+                    .or_default()
+                    .push(comment.text),
+                (Some(block), _) => comments
+                    .dangling
+                    .entry(block.owner)
+                    .or_default()
+                    .push(comment.text),
+                (None, Some(next)) => comments
+                    .leading
+                    .entry(*next)
+                    .or_default()
+                    .push(comment.text),
+                (None, None) => comments.remaining.push(comment.text),
+            }
+        }
+
+        comments
+    }
+}
+
+fn advance(pos: &mut Pos, c: char) {
+    match c {
+        '\n' => {
+            pos.line += 1;
+            pos.column = 1;
+        }
+        '\r' | '\u{feff}' => {}
+        '\t' => pos.column += 8,
+        _ => pos.column += 1,
+    }
+}
+
+fn skip_string<I: Iterator<Item = char>>(chars: &mut std::iter::Peekable<I>, pos: &mut Pos) {
+    while let Some(&c) = chars.peek() {
+        if c == '\n' {
+            return;
+        }
+        chars.next();
+        advance(pos, c);
+
+        match c {
+            '"' => return,
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    advance(pos, escaped);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn skip_block_string<I: Iterator<Item = char> + Clone>(
+    chars: &mut std::iter::Peekable<I>,
+    pos: &mut Pos,
+) {
+    let mut quotes = 0;
+    while let Some(c) = chars.next() {
+        advance(pos, c);
+        match c {
+            '"' => {
+                quotes += 1;
+                if quotes == 3 {
+                    return;
+                }
+            }
+            '\\' if chars.clone().take(3).collect::<String>() == "\"\"\"" => {
+                for _ in 0..3 {
+                    advance(pos, chars.next().unwrap());
+                }
+                quotes = 0;
+            }
+            _ => quotes = 0,
+        }
+    }
+}
+
+/// Comments attached to the nodes of a document.
+#[derive(Debug, Default)]
+pub struct Comments {
+    leading: HashMap<Pos, Vec<String>>,
+    trailing: HashMap<Pos, String>,
+    dangling: HashMap<Pos, Vec<String>>,
+    remaining: Vec<String>,
+}
+
+impl Comments {
+    pub fn leading(&mut self, pos: Pos) -> Vec<String> {
+        self.leading.remove(&pos).unwrap_or_default()
+    }
+
+    pub fn trailing(&mut self, pos: Pos) -> Option<String> {
+        self.trailing.remove(&pos)
+    }
+
+    pub fn dangling(&mut self, pos: Pos) -> Vec<String> {
+        self.dangling.remove(&pos).unwrap_or_default()
+    }
+
+    /// Comments after the last node, and those whose node was never written.
+    pub fn remaining(&mut self) -> Vec<String> {
+        let mut rest = self
+            .leading
+            .drain()
+            .chain(self.dangling.drain())
+            .chain(self.trailing.drain().map(|(pos, text)| (pos, vec![text])))
+            .collect::<Vec<_>>();
+        rest.sort_unstable_by_key(|(pos, _)| *pos);
+
+        let mut remaining = rest
+            .into_iter()
+            .flat_map(|(_, texts)| texts)
+            .collect::<Vec<_>>();
+        remaining.append(&mut self.remaining);
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pos(line: usize, column: usize) -> Pos {
+        Pos { line, column }
+    }
+
+    #[test]
+    fn lex_skips_strings() {
+        let trivia = Trivia::lex(
+            r##"
+a(b: "# no", c: """
+# no \""" still no
+""") # yes
+"##
+            .trim(),
+        );
+
+        assert_eq!(
+            trivia.comments,
+            vec![Comment {
+                pos: pos(3, 6),
+                text: "# yes".to_string(),
+                trailing: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn body_after_skips_arguments() {
+        let trivia = Trivia::lex("type A @key(a: {b: 1}) {\n  a: Int\n}\ntype B { b: Int }");
+
+        let body = trivia.body_after(pos(1, 1)).unwrap();
+        assert_eq!((body.start, body.end), (pos(1, 24), pos(3, 1)));
+        let body = trivia.body_after(pos(4, 1)).unwrap();
+        assert_eq!((body.start, body.end), (pos(4, 8), pos(4, 17)));
+    }
+
+    #[test]
+    fn keys_and_members() {
+        let trivia = Trivia::lex("{ a(b: {c: 1}, d: [E]) @f(g: 2) }\nunion U @h(i: 1) = J\n  | K");
+
+        let arguments = trivia.arguments_after(pos(1, 3)).unwrap();
+        assert_eq!((arguments.start, arguments.end), (pos(1, 4), pos(1, 22)));
+        let keys = trivia.keys(arguments.start);
+        assert_eq!(keys, vec![(pos(1, 5), "b"), (pos(1, 16), "d")]);
+        assert_eq!(
+            trivia.union_members(pos(2, 1), &["J", "K"]),
+            vec![pos(2, 20), pos(3, 5)]
+        );
+    }
+
+    #[test]
+    fn attach() {
+        let source = "
+# leading
+a { # trailing
+  b(c: 1) # also trailing
+  # dangling
+}
+# remaining
+        "
+        .trim();
+        let block = Block {
+            owner: pos(2, 1),
+            start: pos(2, 3),
+            end: pos(5, 1),
+        };
+        let mut comments = Trivia::lex(source).attach(&[pos(2, 1), pos(3, 3), pos(3, 5)], &[block]);
+
+        assert_eq!(comments.leading(pos(2, 1)), vec!["# leading"]);
+        assert_eq!(comments.trailing(pos(2, 1)), Some("# trailing".to_string()));
+        assert_eq!(
+            comments.trailing(pos(3, 3)),
+            Some("# also trailing".to_string())
+        );
+        assert_eq!(comments.dangling(pos(2, 1)), vec!["# dangling"]);
+        assert_eq!(comments.remaining(), vec!["# remaining"]);
+    }
+}
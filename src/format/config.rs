@@ -0,0 +1,214 @@
+use failure::{bail, format_err, Error};
+use serde_derive::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const CONFIG_FILE_NAME: &str = ".gqltools.toml";
+
+/// Formatter settings, read from a `.gqltools.toml` file such as
+///
+/// ```toml
+/// line_width = 100
+/// indent_size = 4
+/// trailing_commas = false
+///
+/// [sort]
+/// selections = false
+/// ```
+///
+/// Settings that aren't in the file keep their defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The width lines are wrapped at.
+    pub line_width: usize,
+    /// The number of spaces per indentation level.
+    pub indent_size: usize,
+    /// Whether the last item of a wrapped argument list, object or list gets a comma.
+    pub trailing_commas: bool,
+    pub sort: Sort,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            line_width: 80,
+            indent_size: 2,
+            trailing_commas: true,
+            sort: Sort::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Look for a config file in `dir` and its parents. Without one the defaults are used.
+    pub fn find(dir: &Path) -> Result<Config, Error> {
+        match find_file(dir) {
+            Some(path) => Config::from_file(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, Error> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format_err!("{}: {}", path.display(), err))?;
+        contents
+            .parse()
+            .map_err(|err| format_err!("{}: {}", path.display(), err))
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Config, Error> {
+        let config: Config = toml::from_str(s)?;
+        if config.line_width == 0 {
+            bail!("line_width must be greater than 0");
+        }
+        Ok(config)
+    }
+}
+
+fn find_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Which kinds of nodes get sorted. The nodes that aren't sorted keep their source order.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sort {
+    /// Fields of object types, interfaces and input objects.
+    pub fields: bool,
+    pub enum_values: bool,
+    pub union_members: bool,
+    /// Arguments of fields and directives, in both queries and schemas.
+    pub arguments: bool,
+    /// Selections in queries. Sorting them changes the key order of the response.
+    pub selections: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Sort {
+        Sort {
+            fields: true,
+            enum_values: true,
+            union_members: true,
+            arguments: true,
+            selections: true,
+        }
+    }
+}
+
+impl Sort {
+    /// Keep the source order of the given kind of nodes.
+    pub fn preserve(&mut self, kind: SortKind) {
+        match kind {
+            SortKind::Fields => self.fields = false,
+            SortKind::EnumValues => self.enum_values = false,
+            SortKind::UnionMembers => self.union_members = false,
+            SortKind::Arguments => self.arguments = false,
+            SortKind::Selections => self.selections = false,
+            SortKind::All => {
+                *self = Sort {
+                    fields: false,
+                    enum_values: false,
+                    union_members: false,
+                    arguments: false,
+                    selections: false,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKind {
+    Fields,
+    EnumValues,
+    UnionMembers,
+    Arguments,
+    Selections,
+    All,
+}
+
+impl FromStr for SortKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SortKind, Error> {
+        Ok(match s {
+            "fields" => SortKind::Fields,
+            "enum-values" => SortKind::EnumValues,
+            "union-members" => SortKind::UnionMembers,
+            "arguments" => SortKind::Arguments,
+            "selections" => SortKind::Selections,
+            "all" => SortKind::All,
+            _ => bail!(
+                "unknown kind \"{}\", expected one of fields, enum-values, union-members, \
+                 arguments, selections or all",
+                s
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn preserve() {
+        let mut sort = Sort::default();
+        sort.preserve("enum-values".parse().unwrap());
+
+        assert!(!sort.enum_values);
+        assert!(sort.fields);
+
+        sort.preserve("all".parse().unwrap());
+        assert!(!sort.fields);
+    }
+
+    #[test]
+    fn unknown_kind() {
+        assert!("enum_values".parse::<SortKind>().is_err());
+    }
+
+    #[test]
+    fn parse_config() {
+        let config: Config = "
+line_width = 100
+trailing_commas = false
+
+[sort]
+selections = false
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(config.line_width, 100);
+        assert_eq!(config.indent_size, 2);
+        assert!(!config.trailing_commas);
+        assert!(!config.sort.selections);
+        assert!(config.sort.fields);
+    }
+
+    #[test]
+    fn parse_config_unknown_setting() {
+        assert!("max_width = 100".parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn find_config_in_parent() {
+        let dir = std::env::temp_dir().join(format!("gqltools-config-{}", std::process::id()));
+        let nested = dir.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.join(CONFIG_FILE_NAME), "indent_size = 4").unwrap();
+
+        let config = Config::find(&nested);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.unwrap().indent_size, 4);
+    }
+}
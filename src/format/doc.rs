@@ -0,0 +1,342 @@
+//! A document layout engine in the style of Wadler's "prettier printer".
+//!
+//! The formatters describe their output as a `Doc`, and the printer decides where lines break.
+//! A `group` is printed flat, with its `line`s as spaces and `softline`s as nothing, if it fits
+//! in the rest of the line. Otherwise all of its direct lines become newlines, indented to the
+//! level of the enclosing `nest`s, and its inner groups get to choose for themselves.
+
+use super::config::Config;
+use super::{Indentation, Output};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Doc {
+    Nil,
+    /// Text without newlines.
+    Text(String),
+    /// A space, or a newline if the enclosing group is broken. Soft lines are empty when flat.
+    Line {
+        soft: bool,
+    },
+    /// A newline that breaks all enclosing groups.
+    HardLine,
+    /// A hard line inside a block string, which line suffixes aren't printed before.
+    LiteralLine,
+    Concat(Vec<Doc>),
+    Nest(Box<Doc>),
+    Group {
+        doc: Box<Doc>,
+        broken: bool,
+    },
+    /// Printed before the next newline, like a trailing comment.
+    LineSuffix(String),
+    /// Chooses between two docs, depending on whether the enclosing group is broken.
+    IfBreak {
+        broken: Box<Doc>,
+        flat: Box<Doc>,
+    },
+    /// Breaks all enclosing groups.
+    BreakParent,
+}
+
+pub fn nil() -> Doc {
+    Doc::Nil
+}
+
+pub fn text<T: Into<String>>(text: T) -> Doc {
+    let text = text.into();
+    debug_assert!(!text.contains('\n'), "text with newlines: {:?}", text);
+    Doc::Text(text)
+}
+
+pub fn line() -> Doc {
+    Doc::Line { soft: false }
+}
+
+pub fn softline() -> Doc {
+    Doc::Line { soft: true }
+}
+
+pub fn hardline() -> Doc {
+    Doc::HardLine
+}
+
+pub fn literal_line() -> Doc {
+    Doc::LiteralLine
+}
+
+pub fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+pub fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+pub fn group(doc: Doc) -> Doc {
+    let broken = doc.has_forced_break();
+    Doc::Group {
+        doc: Box::new(doc),
+        broken,
+    }
+}
+
+pub fn line_suffix<T: Into<String>>(text: T) -> Doc {
+    Doc::LineSuffix(text.into())
+}
+
+pub fn if_break(broken: Doc, flat: Doc) -> Doc {
+    Doc::IfBreak {
+        broken: Box::new(broken),
+        flat: Box::new(flat),
+    }
+}
+
+pub fn break_parent() -> Doc {
+    Doc::BreakParent
+}
+
+pub fn join(docs: Vec<Doc>, separator: Doc) -> Doc {
+    let mut joined = Vec::with_capacity(docs.len() * 2);
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i > 0 {
+            joined.push(separator.clone());
+        }
+        joined.push(doc);
+    }
+    concat(joined)
+}
+
+impl Doc {
+    /// Whether the doc contains a hard line or a break parent, which no group around it can
+    /// print flat. For an `IfBreak` only the flat doc matters, since the broken one is only
+    /// printed in broken groups anyway.
+    fn has_forced_break(&self) -> bool {
+        match self {
+            Doc::HardLine | Doc::LiteralLine | Doc::BreakParent => true,
+            Doc::Concat(docs) => docs.iter().any(Doc::has_forced_break),
+            Doc::Nest(doc) => doc.has_forced_break(),
+            Doc::Group { broken, .. } => *broken,
+            Doc::IfBreak { flat, .. } => flat.has_forced_break(),
+            Doc::Nil | Doc::Text(_) | Doc::Line { .. } | Doc::LineSuffix(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+enum Command<'a> {
+    Print(Mode, &'a Doc),
+    Dedent,
+}
+
+pub fn print(doc: &Doc, config: &Config) -> String {
+    let mut out = Output::new();
+    let mut indent = Indentation::new(config.indent_size);
+    let mut column = 0;
+    let mut suffixes = Vec::new();
+    let mut commands = vec![Command::Print(Mode::Break, doc)];
+
+    while let Some(command) = commands.pop() {
+        let (mode, doc) = match command {
+            Command::Print(mode, doc) => (mode, doc),
+            Command::Dedent => {
+                indent.decrement();
+                continue;
+            }
+        };
+
+        match doc {
+            Doc::Nil | Doc::BreakParent => {}
+            Doc::Text(text) => {
+                out.push_str(text);
+                column += width(text);
+            }
+            Doc::Concat(docs) => {
+                commands.extend(docs.iter().rev().map(|doc| Command::Print(mode, doc)));
+            }
+            Doc::Nest(doc) => {
+                indent.increment();
+                commands.push(Command::Dedent);
+                commands.push(Command::Print(mode, doc));
+            }
+            Doc::Group { doc, broken } => {
+                let remaining = config.line_width as isize - column as isize;
+                let flat = !broken && fits(doc, &commands, remaining);
+                let mode = if flat { Mode::Flat } else { Mode::Break };
+                commands.push(Command::Print(mode, doc));
+            }
+            Doc::IfBreak { broken, flat } => match mode {
+                Mode::Break => commands.push(Command::Print(mode, broken)),
+                Mode::Flat => commands.push(Command::Print(mode, flat)),
+            },
+            Doc::LineSuffix(text) => suffixes.push(text.as_str()),
+            Doc::Line { soft } if mode == Mode::Flat => {
+                if !soft {
+                    out.push_str(" ");
+                    column += 1;
+                }
+            }
+            Doc::Line { .. } | Doc::HardLine | Doc::LiteralLine => {
+                if *doc != Doc::LiteralLine {
+                    for suffix in suffixes.drain(..) {
+                        out.push_str(suffix);
+                    }
+                }
+                out.trim_line_end();
+                out.push_str("\n");
+                out.push("", &indent);
+                column = indent.spaces().len();
+            }
+        }
+    }
+
+    for suffix in suffixes {
+        out.push_str(suffix);
+    }
+
+    out.trim().to_string()
+}
+
+/// Whether `doc` printed flat, followed by the rest of the commands up to their first line
+/// break, fits in `remaining` columns.
+fn fits(doc: &Doc, rest: &[Command], mut remaining: isize) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev().filter_map(|command| match command {
+        Command::Print(mode, doc) => Some((*mode, *doc)),
+        Command::Dedent => None,
+    });
+
+    loop {
+        let (mode, doc) = match stack.pop().or_else(|| rest.next()) {
+            Some(next) => next,
+            None => return true,
+        };
+
+        match doc {
+            Doc::Nil | Doc::LineSuffix(_) | Doc::BreakParent => {}
+            Doc::Text(text) => {
+                remaining -= width(text) as isize;
+                if remaining < 0 {
+                    return false;
+                }
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            Doc::Nest(doc) => stack.push((mode, doc)),
+            Doc::Group { doc, broken } => {
+                let mode = if *broken { Mode::Break } else { mode };
+                stack.push((mode, doc));
+            }
+            Doc::IfBreak { broken, flat } => match mode {
+                Mode::Break => stack.push((mode, broken)),
+                Mode::Flat => stack.push((mode, flat)),
+            },
+            Doc::HardLine | Doc::LiteralLine => return true,
+            Doc::Line { .. } if mode == Mode::Break => return true,
+            Doc::Line { soft } => {
+                if !soft {
+                    remaining -= 1;
+                    if remaining < 0 {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn print_width(doc: &Doc, line_width: usize) -> String {
+        let config = Config {
+            line_width,
+            ..Config::default()
+        };
+        print(doc, &config)
+    }
+
+    fn call(args: &[&str]) -> Doc {
+        let args = args.iter().map(|arg| text(*arg)).collect();
+        group(concat(vec![
+            text("call("),
+            nest(concat(vec![
+                softline(),
+                join(args, concat(vec![text(","), line()])),
+            ])),
+            softline(),
+            text(")"),
+        ]))
+    }
+
+    #[test]
+    fn group_fits() {
+        assert_eq!(print_width(&call(&["a", "b"]), 80), "call(a, b)");
+    }
+
+    #[test]
+    fn group_breaks() {
+        assert_eq!(print_width(&call(&["a", "b"]), 8), "call(\n  a,\n  b\n)");
+    }
+
+    #[test]
+    fn hard_line_breaks_group() {
+        let doc = group(concat(vec![
+            text("a"),
+            line(),
+            text("b"),
+            hardline(),
+            text("c"),
+        ]));
+        assert_eq!(print_width(&doc, 80), "a\nb\nc");
+    }
+
+    #[test]
+    fn rest_of_line_counts() {
+        let doc = concat(vec![call(&["a"]), text(" and some more")]);
+        assert_eq!(print_width(&doc, 12), "call(\n  a\n) and some more");
+    }
+
+    #[test]
+    fn line_suffix_before_newline() {
+        let doc = concat(vec![
+            text("a"),
+            line_suffix(" # comment"),
+            text(","),
+            hardline(),
+            text("b"),
+        ]);
+        assert_eq!(print_width(&doc, 80), "a, # comment\nb");
+    }
+
+    #[test]
+    fn line_suffix_after_literal_line() {
+        let doc = concat(vec![
+            line_suffix(" # comment"),
+            text("\"\"\""),
+            literal_line(),
+            text("\"\"\""),
+            hardline(),
+        ]);
+        assert_eq!(print_width(&doc, 80), "\"\"\"\n\"\"\" # comment");
+    }
+
+    #[test]
+    fn if_break_depends_on_group() {
+        let doc = group(concat(vec![
+            text("a"),
+            line(),
+            if_break(text("broken"), text("flat")),
+        ]));
+        assert_eq!(print_width(&doc, 80), "a flat");
+        assert_eq!(print_width(&doc, 4), "a\nbroken");
+    }
+}
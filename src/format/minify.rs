@@ -0,0 +1,297 @@
+//! Queries on a single line, with no whitespace, commas or comments that aren't needed, for
+//! embedding them in persisted query payloads and URLs.
+//!
+//! Minifying sorts nothing, so the minified query parses to the same AST as the original one.
+//! The canonical form of a query is minified too, but with the selections and arguments
+//! sorted, so that queries which only differ in formatting and order have the same one.
+
+use super::config::{Sort, SortKind};
+use super::parse::parse_query;
+use super::{escape_string, float_value, verify};
+use crate::ast::query::*;
+use failure::Error;
+
+pub fn minify(contents: &str) -> Result<String, Error> {
+    minify_document(parse_query(contents)?)
+}
+
+/// Minify a parsed document, like one put together from the definitions of other documents.
+pub fn minify_document(doc: Document) -> Result<String, Error> {
+    let minified = print(&doc);
+
+    let mut sort = Sort::default();
+    sort.preserve(SortKind::All);
+    verify::verify_query(doc, &minified, &sort)?;
+    Ok(minified)
+}
+
+/// The canonical form of a query, which is minified with the selections and arguments sorted
+/// the way the formatter sorts them by default. Settings from config files aren't used, so
+/// that the canonical form is the same everywhere.
+pub fn canonical(contents: &str) -> Result<String, Error> {
+    let ast = parse_query(contents)?;
+    let sort = Sort::default();
+    let canonical = print(&verify::normalize_query(ast.clone(), &sort));
+
+    verify::verify_query(ast, &canonical, &sort)?;
+    Ok(canonical)
+}
+
+fn print(doc: &Document) -> String {
+    let mut out = Minified::default();
+    for def in &doc.definitions {
+        minify_def(def, &mut out);
+    }
+    out.buf
+}
+
+/// The output, which puts a space between tokens only where they would run together.
+#[derive(Default)]
+struct Minified {
+    buf: String,
+    after_number: bool,
+}
+
+impl Minified {
+    fn push(&mut self, token: &str) {
+        let needs_space = match (self.buf.chars().last(), token.chars().next()) {
+            (Some(_), Some(first)) if self.after_number => !ends_number(first),
+            (Some(last), Some(first)) => is_name_char(last) && is_name_char(first),
+            _ => false,
+        };
+        if needs_space {
+            self.buf.push(' ');
+        }
+        self.buf.push_str(token);
+        self.after_number = token.starts_with(|c: char| c == '-' || c.is_ascii_digit());
+    }
+}
+
+/// Whether `c` can be part of a name, which must be separated from the next one.
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether `c` ends a number for the graphql_parser tokenizer, which reads `1"a"` or `1-2` as
+/// a single, invalid number.
+fn ends_number(c: char) -> bool {
+    "!$:=@|&()[]{}".contains(c)
+}
+
+fn minify_def(def: &Definition, out: &mut Minified) {
+    match def {
+        Definition::Operation(OperationDefinition::SelectionSet(set)) => {
+            minify_selection_set(set, out)
+        }
+        Definition::Operation(OperationDefinition::Query(query)) => minify_operation(
+            "query",
+            &query.name,
+            &query.variable_definitions,
+            &query.directives,
+            &query.selection_set,
+            out,
+        ),
+        Definition::Operation(OperationDefinition::Mutation(mutation)) => minify_operation(
+            "mutation",
+            &mutation.name,
+            &mutation.variable_definitions,
+            &mutation.directives,
+            &mutation.selection_set,
+            out,
+        ),
+        Definition::Operation(OperationDefinition::Subscription(sub)) => minify_operation(
+            "subscription",
+            &sub.name,
+            &sub.variable_definitions,
+            &sub.directives,
+            &sub.selection_set,
+            out,
+        ),
+        Definition::Fragment(frag) => {
+            out.push("fragment");
+            out.push(&frag.name);
+            out.push("on");
+            let TypeCondition::On(type_name) = &frag.type_condition;
+            out.push(type_name);
+            minify_directives(&frag.directives, out);
+            minify_selection_set(&frag.selection_set, out);
+        }
+    }
+}
+
+fn minify_operation(
+    keyword: &str,
+    name: &Option<String>,
+    vars: &[VariableDefinition],
+    directives: &[Directive],
+    selection_set: &SelectionSet,
+    out: &mut Minified,
+) {
+    out.push(keyword);
+    if let Some(name) = name {
+        out.push(name);
+    }
+
+    if !vars.is_empty() {
+        out.push("(");
+        for var in vars {
+            out.push(&format!("${}", var.name));
+            out.push(":");
+            out.push(&var.var_type.to_string());
+            if let Some(default) = &var.default_value {
+                out.push("=");
+                minify_value(default, out);
+            }
+        }
+        out.push(")");
+    }
+
+    minify_directives(directives, out);
+    minify_selection_set(selection_set, out);
+}
+
+fn minify_selection_set(set: &SelectionSet, out: &mut Minified) {
+    out.push("{");
+    for selection in &set.items {
+        match selection {
+            Selection::Field(field) => {
+                if let Some(alias) = &field.alias {
+                    out.push(alias);
+                    out.push(":");
+                }
+                out.push(&field.name);
+                minify_arguments(&field.arguments, out);
+                minify_directives(&field.directives, out);
+                if !field.selection_set.items.is_empty() {
+                    minify_selection_set(&field.selection_set, out);
+                }
+            }
+            Selection::FragmentSpread(frag_spread) => {
+                out.push("...");
+                out.push(&frag_spread.fragment_name);
+                minify_directives(&frag_spread.directives, out);
+            }
+            Selection::InlineFragment(inline_frag) => {
+                out.push("...");
+                if let Some(TypeCondition::On(type_name)) = &inline_frag.type_condition {
+                    out.push("on");
+                    out.push(type_name);
+                }
+                minify_directives(&inline_frag.directives, out);
+                minify_selection_set(&inline_frag.selection_set, out);
+            }
+        }
+    }
+    out.push("}");
+}
+
+fn minify_arguments(args: &[(String, Value)], out: &mut Minified) {
+    if args.is_empty() {
+        return;
+    }
+
+    out.push("(");
+    for (name, value) in args {
+        out.push(name);
+        out.push(":");
+        minify_value(value, out);
+    }
+    out.push(")");
+}
+
+fn minify_directives(directives: &[Directive], out: &mut Minified) {
+    for directive in directives {
+        out.push(&format!("@{}", directive.name));
+        minify_arguments(&directive.arguments, out);
+    }
+}
+
+fn minify_value(value: &Value, out: &mut Minified) {
+    match value {
+        Value::Object(fields) => {
+            out.push("{");
+            for (name, value) in fields {
+                out.push(name);
+                out.push(":");
+                minify_value(value, out);
+            }
+            out.push("}");
+        }
+        Value::List(items) => {
+            out.push("[");
+            for item in items {
+                minify_value(item, out);
+            }
+            out.push("]");
+        }
+        Value::String(s) => out.push(&format!("\"{}\"", escape_string(s))),
+        Value::Float(f) => out.push(&float_value(*f)),
+        _ => out.push(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn operations() {
+        assert_eq!(
+            minify(
+                r#"
+# Users
+query Users($first: Int = 10, $role: [Role!]) @cached(ttl: 60) {
+  users(first: $first, filter: {role: $role, name: "a \"b\""}) {
+    id, # The id
+    name: fullName
+    ...userFields @include(if: true)
+    ... on Admin { level }
+  }
+}
+
+fragment userFields on User { email }
+                "#
+            )
+            .unwrap(),
+            r#"query Users($first:Int=10$role:[Role!])@cached(ttl:60){users(first:$first filter:{name:"a \"b\""role:$role}){id name:fullName...userFields@include(if:true)...on Admin{level}}}fragment userFields on User{email}"#
+        );
+    }
+
+    #[test]
+    fn keeps_the_order() {
+        assert_eq!(minify("{ b a(y: 1, x: 2.0) }").unwrap(), "{b a(y:1 x:2.0)}");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            minify("{ a(b: [1, -2, 1.5, \"c\", 2e3], d: 1) }").unwrap(),
+            r#"{a(b:[1 -2 1.5 "c"2000.0]d:1)}"#
+        );
+    }
+
+    #[test]
+    fn block_strings() {
+        assert_eq!(
+            minify("mutation { post(body: \"\"\"\n  First\n  Second\n\"\"\") }").unwrap(),
+            r#"mutation{post(body:"First\nSecond")}"#
+        );
+    }
+
+    #[test]
+    fn canonical_form() {
+        let canonical = canonical("query Q { b(y: 1, x: 2) ...F a { d c } }").unwrap();
+
+        assert_eq!(canonical, "query Q{b(x:2 y:1)a{c d}...F}");
+        assert_eq!(
+            super::canonical("# Q\nquery Q {\n  ...F\n  b(x: 2, y: 1)\n  a { c, d }\n}").unwrap(),
+            canonical
+        );
+    }
+
+    #[test]
+    fn unnamed_operations() {
+        assert_eq!(minify("query { a }").unwrap(), "query{a}");
+        assert_eq!(minify("{ a } { b }").unwrap(), "{a}{b}");
+    }
+}
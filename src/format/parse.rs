@@ -0,0 +1,194 @@
+//! Parsing with the block string values of the spec.
+//!
+//! graphql_parser keeps the newline at the end of a block string, and the blank lines in it
+//! after the first line, so `"""\n  a\n"""` parses as `"a\n"` instead of `"a"`. Documents are
+//! parsed after replacing every block string with a quoted string holding its value, so that
+//! formatting, minifying and hashing don't change what a string means.
+
+use super::escape_string;
+use crate::ast::{query, schema};
+
+pub fn parse_query(source: &str) -> Result<query::Document, query::ParseError> {
+    graphql_parser::parse_query::<String>(&quote_block_strings(source)).map(|doc| doc.into_static())
+}
+
+pub fn parse_schema(source: &str) -> Result<schema::Document, schema::ParseError> {
+    graphql_parser::parse_schema::<String>(&quote_block_strings(source))
+        .map(|doc| doc.into_static())
+}
+
+/// The value of a block string with the `raw` text between the quotes: the indentation common
+/// to the lines after the first is removed, and so are the blank lines at the start and end.
+pub fn block_string_value(raw: &str) -> String {
+    let raw = raw
+        .replace("\\\"\"\"", "\"\"\"")
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    let lines = raw.split('\n').collect::<Vec<_>>();
+
+    let indent = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let common_indent = lines[1..]
+        .iter()
+        .filter(|line| indent(line) < line.len())
+        .map(|line| indent(line))
+        .min()
+        .unwrap_or(0);
+
+    let mut lines = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line
+            } else {
+                &line[common_indent.min(line.len())..]
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let is_blank = |line: &&str| line.trim_start_matches([' ', '\t']).is_empty();
+    while lines.first().is_some_and(is_blank) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(is_blank) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// `source` with its block strings replaced by quoted strings. Each quoted string is padded
+/// with the lines and spaces its block string took up, so that the tokens after it keep their
+/// positions, unless escaping makes a block string on a single line longer.
+///
+/// Block strings the parser rejects, because they aren't terminated or hold control
+/// characters, are left for it to report.
+fn quote_block_strings(source: &str) -> String {
+    let mut quoted = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find(['"', '#']) {
+        quoted.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let len = if rest.starts_with('#') {
+            rest.find(['\n', '\r']).unwrap_or(rest.len())
+        } else if rest.starts_with("\"\"\"") {
+            let raw = match block_string_raw(&rest[3..]) {
+                Some(raw) => raw,
+                None => break,
+            };
+            let len = raw.len() + 6;
+            if raw
+                .chars()
+                .any(|c| c.is_control() && c != '\t' && c != '\n' && c != '\r')
+            {
+                quoted.push_str(&rest[..len]);
+            } else {
+                let string = format!("\"{}\"", escape_string(&block_string_value(raw)));
+                quoted.push_str(&padded(&string, &rest[..len]));
+            }
+            rest = &rest[len..];
+            continue;
+        } else {
+            string_len(rest)
+        };
+
+        quoted.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    quoted.push_str(rest);
+    quoted
+}
+
+/// The text between the quotes of the block string that `tail` starts with, after its opening
+/// quotes. The string ends at the first `"""` that isn't escaped, like in the parser.
+fn block_string_raw(tail: &str) -> Option<&str> {
+    tail.match_indices("\"\"\"")
+        .map(|(end, _)| &tail[..end])
+        .find(|raw| !raw.ends_with('\\'))
+}
+
+/// The length of the quoted string that `rest` starts with, up to the end of the line if it
+/// isn't terminated.
+fn string_len(rest: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return i + 1,
+            '\n' | '\r' => return i,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    rest.len()
+}
+
+/// `string` followed by the newlines of `original`, and by spaces up to the column where
+/// `original` ends. The parser counts every character in a string as one column.
+fn padded(string: &str, original: &str) -> String {
+    match original.rfind('\n') {
+        Some(end) => format!(
+            "{}{}{}",
+            string,
+            "\n".repeat(original.matches('\n').count()),
+            " ".repeat(original[end + 1..].chars().count())
+        ),
+        None => {
+            let width = original.chars().count();
+            format!("{:1$}", string, width)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn block_string_values() {
+        assert_eq!(block_string_value("\n  First\n  Second\n"), "First\nSecond");
+        assert_eq!(block_string_value("\n\n  a\n\n    b\n  \n"), "a\n\n  b");
+        assert_eq!(block_string_value("first\n    second"), "first\nsecond");
+        assert_eq!(block_string_value("  kept \\\"\"\" "), "  kept \"\"\" ");
+        assert_eq!(block_string_value("\r\n\ta\r\n\t\tb\r\n"), "a\n\tb");
+        assert_eq!(block_string_value(" \n \t"), "");
+    }
+
+    #[test]
+    fn quotes_block_strings_in_place() {
+        let source = r#"{ a(s: """
+    x
+  """ t: "\"\"\"", u: """y""" v: 1) }"#;
+
+        assert_eq!(
+            quote_block_strings(source),
+            r#"{ a(s: "x"
+
+      t: "\"\"\"", u: "y"     v: 1) }"#
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_unterminated_strings() {
+        let source = "# \"\"\"\n{ a(s: \"\"\"b) }";
+
+        assert_eq!(quote_block_strings(source), source);
+    }
+
+    #[test]
+    fn keeps_positions() {
+        let doc = parse_query("{ a(s: \"\"\"\n  x\n\"\"\") b }").unwrap();
+        let set = match &doc.definitions[0] {
+            query::Definition::Operation(query::OperationDefinition::SelectionSet(set)) => set,
+            _ => unreachable!(),
+        };
+
+        match &set.items[1] {
+            query::Selection::Field(field) => {
+                assert_eq!(field.position, graphql_parser::Pos { line: 3, column: 6 })
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -1,97 +1,204 @@
-use super::{map_join, Indentation, Output, INDENT_SIZE, MAX_LINE_LENGTH};
+use super::comments::{Block, Positions, Trivia};
+use super::config::Config;
+use super::doc::{self, *};
+use super::{
+    argument_list, blank_line, block, format_directives, format_value, list, parse, verify, Context,
+};
+use crate::ast::query::*;
 use failure::{bail, Error};
-use graphql_parser::{parse_query, query::*};
+use graphql_parser::Pos;
 
-pub fn format(contents: &str) -> Result<String, Error> {
-    let ast = parse_query(contents)?;
+pub fn format(contents: &str, config: &Config) -> Result<String, Error> {
+    let ast = parse::parse_query(contents)?;
 
-    let mut out = Output::new();
-    let mut indent = Indentation::new(INDENT_SIZE);
-    format_doc(ast, &mut indent, &mut out);
+    let trivia = Trivia::lex(contents);
+    let mut anchors = Vec::new();
+    let mut blocks = Vec::new();
+    let mut positions = Positions::new();
+    for def in &ast.definitions {
+        def_anchors(def, &trivia, &mut anchors, &mut blocks, &mut positions);
+    }
+    let comments = trivia.attach(&anchors, &blocks);
+
+    let mut cx = Context::new(config, comments, positions);
+    let doc = format_doc(ast.clone(), &mut cx);
+    let formatted = doc::print(&doc, config);
 
-    Ok(out.trim().to_string())
+    verify::verify_query(ast, &formatted, &config.sort)?;
+    Ok(formatted)
+}
+
+/// Collect the positions of the nodes that comments are attached to, and the braces of the
+/// selection sets. The positions of arguments are found in `trivia`.
+fn def_anchors(
+    def: &Definition,
+    trivia: &Trivia,
+    anchors: &mut Vec<Pos>,
+    blocks: &mut Vec<Block>,
+    positions: &mut Positions,
+) {
+    let (position, set) = match def {
+        Definition::Operation(OperationDefinition::Query(query)) => {
+            variable_anchors(&query.variable_definitions, anchors);
+            (query.position, &query.selection_set)
+        }
+        Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+            variable_anchors(&mutation.variable_definitions, anchors);
+            (mutation.position, &mutation.selection_set)
+        }
+        Definition::Operation(OperationDefinition::Subscription(sub)) => {
+            variable_anchors(&sub.variable_definitions, anchors);
+            (sub.position, &sub.selection_set)
+        }
+        Definition::Operation(OperationDefinition::SelectionSet(set)) => (set.span.0, set),
+        Definition::Fragment(frag) => (frag.position, &frag.selection_set),
+    };
+    anchors.push(position);
+    selection_set_anchors(set, trivia, anchors, blocks, positions);
 }
 
-fn format_doc(doc: Document, indent: &mut Indentation, out: &mut Output) {
-    for def in doc.definitions {
-        format_def(def, indent, out);
+fn variable_anchors(vars: &[VariableDefinition], anchors: &mut Vec<Pos>) {
+    anchors.extend(vars.iter().map(|var| var.position));
+}
+
+fn selection_set_anchors(
+    set: &SelectionSet,
+    trivia: &Trivia,
+    anchors: &mut Vec<Pos>,
+    blocks: &mut Vec<Block>,
+    positions: &mut Positions,
+) {
+    if set.items.is_empty() {
+        return;
+    }
+    blocks.push(Block {
+        owner: set.span.0,
+        start: set.span.0,
+        end: set.span.1,
+    });
+
+    for selection in &set.items {
+        match selection {
+            Selection::Field(field) => {
+                anchors.push(field.position);
+                if !field.arguments.is_empty() {
+                    if let Some(arguments) = trivia.arguments_after(field.position) {
+                        let args = argument_positions(arguments, trivia);
+                        anchors.extend(&args);
+                        positions.insert(field.position, args);
+                        blocks.push(arguments);
+                    }
+                }
+                selection_set_anchors(&field.selection_set, trivia, anchors, blocks, positions);
+            }
+            Selection::FragmentSpread(frag_spread) => anchors.push(frag_spread.position),
+            Selection::InlineFragment(inline_frag) => {
+                anchors.push(inline_frag.position);
+                let set = &inline_frag.selection_set;
+                selection_set_anchors(set, trivia, anchors, blocks, positions);
+            }
+        }
     }
 }
 
-fn format_def(def: Definition, indent: &mut Indentation, out: &mut Output) {
+/// The positions of the arguments between the parentheses of a field, in the order of the
+/// source.
+fn argument_positions(arguments: Block, trivia: &Trivia) -> Vec<Pos> {
+    trivia
+        .keys(arguments.start)
+        .into_iter()
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+fn format_doc(doc: Document, cx: &mut Context) -> Doc {
+    let defs = doc
+        .definitions
+        .into_iter()
+        .map(|def| format_def(def, cx))
+        .collect();
+    concat(vec![join(defs, blank_line()), cx.remaining_comments()])
+}
+
+fn format_def(def: Definition, cx: &mut Context) -> Doc {
     match def {
-        Definition::Operation(operation) => format_operation(operation, indent, out),
-        Definition::Fragment(fragment) => format_fragment(fragment, indent, out),
+        Definition::Operation(operation) => format_operation(operation, cx),
+        Definition::Fragment(fragment) => format_fragment(fragment, cx),
     }
 }
 
-fn format_operation(op: OperationDefinition, indent: &mut Indentation, out: &mut Output) {
+fn format_operation(op: OperationDefinition, cx: &mut Context) -> Doc {
     match op {
-        OperationDefinition::Query(query) => {
-            format_operation_type(OperationType::Query(query), indent, out)
-        }
+        OperationDefinition::Query(query) => format_operation_type(OperationType::Query(query), cx),
         OperationDefinition::Mutation(query) => {
-            format_operation_type(OperationType::Mutation(query), indent, out)
+            format_operation_type(OperationType::Mutation(query), cx)
+        }
+        OperationDefinition::SelectionSet(set) => {
+            let position = set.span.0;
+            let doc = format_selection_set(set, cx);
+            cx.with_comments(position, doc)
         }
-        OperationDefinition::SelectionSet(set) => format_selection_set(set, indent, out),
         OperationDefinition::Subscription(sub) => {
-            format_operation_type(OperationType::Subscription(sub), indent, out)
+            format_operation_type(OperationType::Subscription(sub), cx)
         }
     }
 }
 
-fn format_fragment(frag: FragmentDefinition, indent: &mut Indentation, out: &mut Output) {
-    out.push(
-        &format!(
+fn format_fragment(frag: FragmentDefinition, cx: &mut Context) -> Doc {
+    let doc = concat(vec![
+        text(format!(
             "fragment {name} {type_}",
             name = frag.name,
             type_ = frag.type_condition
-        ),
-        indent,
-    );
-    format_selection_set(frag.selection_set, indent, out);
+        )),
+        format_directives(&frag.directives, cx),
+        text(" "),
+        format_selection_set(frag.selection_set, cx),
+    ]);
+    cx.with_comments(frag.position, doc)
 }
 
-fn format_operation_type(r#type: OperationType, indent: &mut Indentation, out: &mut Output) {
-    // TODO: directives
+fn format_operation_type(r#type: OperationType, cx: &mut Context) -> Doc {
+    let mut docs = Vec::new();
 
-    let has_name;
     if let Some(name) = r#type.name() {
-        has_name = true;
-        out.push(
-            &format!("{type_} {name}", type_ = r#type.to_string(), name = name),
-            indent,
-        );
+        docs.push(text(format!("{type_} {name}", type_ = r#type, name = name)));
     } else {
-        has_name = false;
-        out.push(&format!("{type_}", type_ = r#type.to_string()), indent);
+        docs.push(text(r#type.to_string()));
     }
 
     if !r#type.variable_definitions().is_empty() {
-        if has_name {
-            out.push_str("(");
-        } else {
-            out.push_str(" (");
+        if r#type.name().is_none() {
+            docs.push(text(" "));
         }
+        let vars = format_variable_definitions(r#type.variable_definitions(), cx);
+        docs.push(vars);
+    }
 
-        map_join(
-            r#type.variable_definitions().iter(),
-            |var| {
-                let mut out = format!("${name}: {type_}", name = var.name, type_ = var.var_type);
-                if let Some(default) = &var.default_value {
-                    out.push_str(&format!(" = {}", default));
-                }
-                out
-            },
-            ", ",
-            out,
-        );
+    docs.push(format_directives(r#type.directives(), cx));
+    docs.push(text(" "));
+    docs.push(format_selection_set(r#type.selection_set().clone(), cx));
 
-        out.push_str(")");
-    }
+    cx.with_comments(r#type.position(), concat(docs))
+}
 
-    format_selection_set(r#type.selection_set().clone(), indent, out);
-    out.push_str("\n");
+fn format_variable_definitions(vars: &[VariableDefinition], cx: &mut Context) -> Doc {
+    let vars = vars
+        .iter()
+        .map(|var| {
+            let mut docs = vec![text(format!(
+                "${name}: {type_}",
+                name = var.name,
+                type_ = var.var_type
+            ))];
+            if let Some(default) = &var.default_value {
+                docs.push(text(" = "));
+                docs.push(format_value(default, cx));
+            }
+            cx.with_comments(var.position, concat(docs))
+        })
+        .collect();
+    list("(", vars, nil(), ")", cx)
 }
 
 enum OperationType {
@@ -111,6 +218,10 @@ macro_rules! get_operation_type_field {
 }
 
 impl OperationType {
+    fn position(&self) -> Pos {
+        *get_operation_type_field!(self, position)
+    }
+
     fn selection_set(&self) -> &SelectionSet {
         get_operation_type_field!(self, selection_set)
     }
@@ -142,114 +253,120 @@ impl std::fmt::Display for OperationType {
     }
 }
 
-fn format_selection_set(set: SelectionSet, indent: &mut Indentation, out: &mut Output) {
+fn format_selection_set(set: SelectionSet, cx: &mut Context) -> Doc {
     let mut items = set.items;
 
     if items.is_empty() {
-        return;
+        return nil();
     }
 
-    out.push_str(" {\n");
-    indent.increment();
-
-    items.sort_unstable_by_key(selection_set_sort_key);
+    if cx.config.sort.selections {
+        items.sort_by_key(selection_set_sort_key);
+    }
 
-    for selection in items {
-        match selection {
-            Selection::Field(field) => format_field(field, indent, out),
+    let selections = items
+        .into_iter()
+        .map(|selection| match selection {
+            Selection::Field(field) => format_field(field, cx),
             Selection::FragmentSpread(frag_spread) => {
-                // TODO: directives
-                out.push(&format!("...{}\n", frag_spread.fragment_name), indent);
-            }
-            Selection::InlineFragment(inline_frag) => {
-                format_inline_fragment(inline_frag, indent, out)
+                let doc = concat(vec![
+                    text(format!("...{}", frag_spread.fragment_name)),
+                    format_directives(&frag_spread.directives, cx),
+                ]);
+                cx.with_comments(frag_spread.position, doc)
             }
-        }
-    }
-    indent.decrement();
-    out.push("}\n", indent);
+            Selection::InlineFragment(inline_frag) => format_inline_fragment(inline_frag, cx),
+        })
+        .collect();
+    let dangling_comments = cx.dangling_comments(set.span.0);
+
+    block(selections, hardline(), dangling_comments)
 }
 
-fn selection_set_sort_key(sel: &Selection) -> (usize, String) {
+/// The order of selections when sorted. Fields with the same name are ordered by their aliases
+/// and arguments, so that the canonical form of a query doesn't depend on their order either.
+pub fn selection_set_sort_key(sel: &Selection) -> (usize, String, Option<String>, Vec<String>) {
     match sel {
-        Selection::FragmentSpread(frag_spread) => (3, frag_spread.fragment_name.clone()),
+        Selection::FragmentSpread(frag_spread) => {
+            (3, frag_spread.fragment_name.clone(), None, Vec::new())
+        }
         Selection::InlineFragment(inline_frag) => {
             if let Some(TypeCondition::On(ref name)) = inline_frag.type_condition {
-                (4, name.clone())
+                (4, name.clone(), None, Vec::new())
             } else {
-                (5, String::new())
+                (5, String::new(), None, Vec::new())
             }
         }
         Selection::Field(field) => {
-            if field.selection_set.items.is_empty() {
-                (1, field.name.clone())
+            let rank = if field.selection_set.items.is_empty() {
+                1
             } else {
-                (2, field.name.clone())
-            }
+                2
+            };
+            let mut args = field
+                .arguments
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>();
+            args.sort();
+            (rank, field.name.clone(), field.alias.clone(), args)
         }
     }
 }
 
-fn format_inline_fragment(inline_frag: InlineFragment, indent: &mut Indentation, out: &mut Output) {
-    // TODO: directives
-
-    out.push("...", indent);
+fn format_inline_fragment(inline_frag: InlineFragment, cx: &mut Context) -> Doc {
+    let mut docs = vec![text("...")];
     if let Some(TypeCondition::On(type_condition)) = inline_frag.type_condition {
-        out.push_str(&format!(" on {}", type_condition));
+        docs.push(text(format!(" on {}", type_condition)));
     }
-    format_selection_set(inline_frag.selection_set, indent, out);
+    docs.push(format_directives(&inline_frag.directives, cx));
+    docs.push(text(" "));
+    docs.push(format_selection_set(inline_frag.selection_set, cx));
+    cx.with_comments(inline_frag.position, concat(docs))
 }
 
-fn format_field(field: Field, indent: &mut Indentation, out: &mut Output) {
-    // TODO: directives
-
+fn format_field(field: Field, cx: &mut Context) -> Doc {
+    let mut docs = Vec::new();
     if let Some(alias) = field.alias {
-        out.push(
-            &format!("{alias}: {name}", alias = alias, name = field.name),
-            indent,
-        );
+        docs.push(text(format!(
+            "{alias}: {name}",
+            alias = alias,
+            name = field.name
+        )));
     } else {
-        out.push(&format!("{name}", name = field.name), indent);
+        docs.push(text(field.name));
     }
 
     if !field.arguments.is_empty() {
-        out.push_str("(");
-        let current_line_length = out.current_line_length();
-
-        let mut args = field
+        let positions = cx.positions(field.position, field.arguments.len());
+        let args = field
             .arguments
             .iter()
-            .map(|(key, value)| format!("{arg}: {value}", arg = key, value = value.to_string()))
-            .collect::<Vec<_>>();
-        args.sort_unstable();
-        let args_joined = args.join(", ") + ")";
-
-        let line_length_with_args = current_line_length + args_joined.len();
-
-        if line_length_with_args > MAX_LINE_LENGTH {
-            indent.increment();
-            out.push_str("\n");
-            args.iter().for_each(|arg| {
-                out.push(&format!("{},\n", arg), indent);
-            });
-            indent.decrement();
-            out.push(")", indent);
-        } else {
-            out.push_str(&args_joined);
-        }
+            .zip(positions)
+            .map(|((name, value), pos)| {
+                let arg = concat(vec![text(format!("{}: ", name)), format_value(value, cx)]);
+                (name.clone(), cx.with_comments(pos, arg))
+            })
+            .collect();
+        let dangling_comments = cx.dangling_comments(field.position);
+        docs.push(argument_list(args, dangling_comments, cx));
     }
 
-    if field.selection_set.items.is_empty() {
-        out.push_str("\n");
-    } else {
-        format_selection_set(field.selection_set, indent, out);
+    docs.push(format_directives(&field.directives, cx));
+
+    if !field.selection_set.items.is_empty() {
+        docs.push(text(" "));
+        docs.push(format_selection_set(field.selection_set, cx));
     }
+
+    cx.with_comments(field.position, concat(docs))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::format::format_test;
+    use crate::format::config::SortKind;
+    use crate::format::{format_test, format_test_with_config};
 
     #[test]
     fn test_basic() {
@@ -280,7 +397,7 @@ query Two { firstName lastName team { id slug league { id } } country { id } }
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query One {
   firstName
@@ -317,7 +434,7 @@ query One { alias: firstName }
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query One {
   alias: firstName
@@ -340,7 +457,7 @@ query Two { firstName(a: \"123\") { id } }
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query One {
   firstName(a: {b: 123, one: ONE}) {
@@ -374,7 +491,7 @@ query UserProfile {
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query UserProfile {
   user(
@@ -411,7 +528,7 @@ mutation NewUser {
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 mutation NewUser {
   newUser(name: \"Bob\") {
@@ -439,7 +556,7 @@ query UserProfile {
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query UserProfile {
   user(a: 1, h: 1, x: 1) {
@@ -472,7 +589,7 @@ query UserProfile ($username:String!
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query UserProfile($username: String!) {
   reddit {
@@ -509,7 +626,7 @@ query UserProfile ($username:String! = \"123\"
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query UserProfile($username: String! = \"123\") {
   reddit {
@@ -546,7 +663,7 @@ query   ($username:String!
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query ($username: String!) {
   reddit {
@@ -587,7 +704,7 @@ query {
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 query {
   a(query: \"hi\") {
@@ -613,6 +730,405 @@ query {
         }
     }
 
+    #[test]
+    fn directives() {
+        format_test(
+            format,
+            "
+query One($withTeam: Boolean!, $skipName: Boolean!) @live {
+  user(id: 1) @cached(ttl: 60) {
+    name @skip(if: $skipName)
+    alias: id @include(if: true)
+    team @include(if: $withTeam) { id }
+    ...userFields @include(if: $withTeam)
+    ... on User @skip(if: $skipName) { slug }
+    ... @include(if: $withTeam) { email }
+  }
+}
+
+fragment userFields on User @tag { id }
+            ",
+            "
+query One($withTeam: Boolean!, $skipName: Boolean!) @live {
+  user(id: 1) @cached(ttl: 60) {
+    alias: id @include(if: true)
+    name @skip(if: $skipName)
+    team @include(if: $withTeam) {
+      id
+    }
+    ...userFields @include(if: $withTeam)
+    ... on User @skip(if: $skipName) {
+      slug
+    }
+    ... @include(if: $withTeam) {
+      email
+    }
+  }
+}
+
+fragment userFields on User @tag {
+  id
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn directives_long_lines() {
+        format_test(
+            format,
+            "
+query One {
+  user(id: 1) { name @formatted(style: \"uppercase\", locale: \"en-US\", fallback: \"anonymous user\") }
+}
+            ",
+            "
+query One {
+  user(id: 1) {
+    name @formatted(
+      fallback: \"anonymous user\",
+      locale: \"en-US\",
+      style: \"uppercase\",
+    )
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn comments() {
+        format_test(
+            format,
+            "
+# The profile page
+query Profile { # trailing
+  # The user
+  user(id: 1) {
+    slug # sorted after id
+    id
+    # dangling
+  }
+  ...meta # spread
+}
+# the end
+            ",
+            "
+# The profile page
+query Profile { # trailing
+  # The user
+  user(id: 1) {
+    id
+    slug # sorted after id
+    # dangling
+  }
+  ...meta # spread
+}
+
+# the end
+            ",
+        );
+    }
+
+    #[test]
+    fn comments_in_arguments() {
+        format_test(
+            format,
+            "
+{
+  user(
+    # The slug
+    slug: \"me\"
+    id: 1 # sorted first
+  ) {
+    id
+  }
+}
+            ",
+            "
+{
+  user(
+    id: 1, # sorted first
+    # The slug
+    slug: \"me\",
+  ) {
+    id
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn comments_in_argument_values() {
+        format_test(
+            format,
+            "
+{
+  f(x: { # inner
+    y: 1 # after y
+  }, z: [
+    # first
+    1
+  ]) { g }
+}
+            ",
+            "
+{
+  f(
+    x: {y: 1}, # inner
+    # after y
+    z: [1],
+    # first
+  ) {
+    g
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn comments_in_strings() {
+        format_test(
+            format,
+            "
+{ user(name: \"# not a comment\") { id } }
+            ",
+            "
+{
+  user(name: \"# not a comment\") {
+    id
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn preserve_order() {
+        let mut config = Config::default();
+        config.sort.preserve(SortKind::Selections);
+        config.sort.preserve(SortKind::Arguments);
+
+        format_test_with_config(
+            format,
+            &config,
+            "
+query One { user(x: 1, a: 1) { ... on User { slug } team { id } name ...userFields } }
+            ",
+            "
+query One {
+  user(x: 1, a: 1) {
+    ... on User {
+      slug
+    }
+    team {
+      id
+    }
+    name
+    ...userFields
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn config() {
+        let config = Config {
+            line_width: 30,
+            indent_size: 4,
+            trailing_commas: false,
+            ..Config::default()
+        };
+
+        format_test_with_config(
+            format,
+            &config,
+            "
+query One { user(id: 1, name: \"a long name\") { id } }
+            ",
+            "
+query One {
+    user(
+        id: 1,
+        name: \"a long name\"
+    ) {
+        id
+    }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn variables_long_lines() {
+        format_test(
+            format,
+            "
+query Search($query: String!, $first: Int = 10, $after: String, $filters: [SearchFilter!]) {
+  search(query: $query) { id }
+}
+
+query Users(
+  # Who to look for
+  $name: String
+  $first: Int # Page size
+) { users(name: $name, first: $first) { id } }
+            ",
+            "
+query Search(
+  $query: String!,
+  $first: Int = 10,
+  $after: String,
+  $filters: [SearchFilter!],
+) {
+  search(query: $query) {
+    id
+  }
+}
+
+query Users(
+  # Who to look for
+  $name: String,
+  $first: Int, # Page size
+) {
+  users(first: $first, name: $name) {
+    id
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn nested_values_long_lines() {
+        format_test(
+            format,
+            "
+query Search {
+  search(first: 10, filter: {and: [{name: {contains: \"something long\"}, role: ADMIN}, {or: [{name: {startsWith: \"something else\"}}, {role: USER}]}]}) { id }
+  tagged(tags: [\"a rather long tag\", \"another rather long tag\", \"and yet another tag\"]) { id }
+  short(filter: {ids: [1, 2, 3]}) { id }
+}
+            ",
+            "
+query Search {
+  search(
+    filter: {
+      and: [
+        {name: {contains: \"something long\"}, role: ADMIN},
+        {or: [{name: {startsWith: \"something else\"}}, {role: USER}]},
+      ],
+    },
+    first: 10,
+  ) {
+    id
+  }
+  short(filter: {ids: [1, 2, 3]}) {
+    id
+  }
+  tagged(
+    tags: [
+      \"a rather long tag\",
+      \"another rather long tag\",
+      \"and yet another tag\",
+    ],
+  ) {
+    id
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn nested_values_without_trailing_commas() {
+        let config = Config {
+            line_width: 40,
+            trailing_commas: false,
+            ..Config::default()
+        };
+
+        format_test_with_config(
+            format,
+            &config,
+            "
+{ users(where: {role: {in: [ADMIN, OWNER]}, name: {eq: \"someone\"}}) { id } }
+            ",
+            "
+{
+  users(
+    where: {
+      name: {eq: \"someone\"},
+      role: {in: [ADMIN, OWNER]}
+    }
+  ) {
+    id
+  }
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn block_string_arguments() {
+        format_test(
+            format,
+            r#"
+{ post(body: """
+  First line
+  Second line
+""") { id } }
+            "#,
+            r#"
+{
+  post(
+    body: """
+    First line
+    Second line
+    """,
+  ) {
+    id
+  }
+}
+            "#,
+        );
+    }
+
+    #[test]
+    fn strings_ending_in_newlines() {
+        format_test(
+            format,
+            r#"{ a(s: "line one\nline two\n", t: "line one\nline two") }"#,
+            r#"
+{
+  a(
+    s: "line one\nline two\n",
+    t: """
+    line one
+    line two
+    """,
+  )
+}
+            "#,
+        );
+    }
+
+    #[test]
+    fn float_arguments() {
+        format_test(
+            format,
+            "{ a(x: 2.0, y: 1e3, z: 0.25) }",
+            "
+{
+  a(x: 2.0, y: 1000.0, z: 0.25)
+}
+            ",
+        );
+    }
+
     #[test]
     fn fragment_definition() {
         let query = "
@@ -630,7 +1146,7 @@ friendsConnection(first: $first) {
         "
         .trim();
 
-        let actual = format(query).unwrap();
+        let actual = format(query, &Config::default()).unwrap();
         let expected = "
 fragment comparisonFields on Character {
   name
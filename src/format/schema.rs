@@ -1,244 +1,628 @@
-use super::{map_join, Indentation, Output, INDENT_SIZE, MAX_LINE_LENGTH};
+use super::comments::{Block, Positions, Trivia};
+use super::config::Config;
+use super::doc::{self, *};
+use super::{
+    argument_list, blank_line, block, block_string, format_directive, format_directives,
+    format_value, is_block_string_safe, parse, quoted_string, verify, Context,
+};
+use crate::ast::schema::*;
 use failure::{bail, Error};
-use graphql_parser::parse_schema;
-use graphql_parser::schema::*;
-use itertools::{Itertools, Position};
+use graphql_parser::Pos;
 
-pub fn format(contents: &str) -> Result<String, Error> {
-    let ast = parse_schema(contents)?;
+/// The operation types of a schema definition, in the order they are formatted in.
+const OPERATIONS: [&str; 3] = ["mutation", "query", "subscription"];
 
-    let mut out = Output::new();
-    let mut indent = Indentation::new(INDENT_SIZE);
+pub fn format(contents: &str, config: &Config) -> Result<String, Error> {
+    let ast = parse::parse_schema(contents)?;
 
-    for def in ast.definitions {
-        format_def(def, &mut indent, &mut out);
+    let trivia = Trivia::lex(contents);
+    let mut anchors = Vec::new();
+    let mut blocks = Vec::new();
+    let mut positions = Positions::new();
+    for def in &ast.definitions {
+        def_anchors(def, &trivia, &mut anchors, &mut blocks, &mut positions);
     }
+    let comments = trivia.attach(&anchors, &blocks);
 
-    Ok(out.trim().to_string())
+    let mut cx = Context::new(config, comments, positions);
+    let defs = ast
+        .definitions
+        .clone()
+        .into_iter()
+        .map(|def| format_def(def, &mut cx))
+        .collect();
+    let doc = concat(vec![join(defs, blank_line()), cx.remaining_comments()]);
+
+    let formatted = doc::print(&doc, config);
+
+    verify::verify_schema(ast, &formatted, &config.sort)?;
+    Ok(formatted)
+}
+
+/// Collect the positions of the nodes that comments are attached to, and the bodies of the
+/// definitions. The positions of union members and operation types are found in `trivia`.
+fn def_anchors(
+    def: &Definition,
+    trivia: &Trivia,
+    anchors: &mut Vec<Pos>,
+    blocks: &mut Vec<Block>,
+    positions: &mut Positions,
+) {
+    let (position, has_body) = match def {
+        Definition::SchemaDefinition(schema_def) => {
+            let pos = schema_def.position;
+            let keys = match trivia.body_after(pos) {
+                Some(body) => trivia.keys(body.start),
+                None => Vec::new(),
+            };
+            let operations = OPERATIONS
+                .iter()
+                .map(|operation| {
+                    keys.iter()
+                        .find(|(_, key)| key == operation)
+                        .map_or(Pos::default(), |(pos, _)| *pos)
+                })
+                .collect::<Vec<_>>();
+            anchors.extend(operations.iter().filter(|pos| **pos != Pos::default()));
+            positions.insert(pos, operations);
+            (pos, true)
+        }
+        Definition::TypeDefinition(TypeDefinition::Object(obj)) => {
+            (obj.position, field_anchors(&obj.fields, anchors))
+        }
+        Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+            (interface.position, field_anchors(&interface.fields, anchors))
+        }
+        Definition::TypeDefinition(TypeDefinition::InputObject(obj)) => {
+            (obj.position, input_value_anchors(&obj.fields, anchors))
+        }
+        Definition::TypeDefinition(TypeDefinition::Enum(enum_)) => {
+            (enum_.position, enum_value_anchors(&enum_.values, anchors))
+        }
+        Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => (scalar.position, false),
+        Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+            let members = trivia.union_members(union.position, &union.types);
+            anchors.extend(&members);
+            positions.insert(union.position, members);
+            (union.position, false)
+        }
+        Definition::TypeExtension(TypeExtension::Object(obj)) => {
+            (obj.position, field_anchors(&obj.fields, anchors))
+        }
+        Definition::TypeExtension(TypeExtension::Interface(interface)) => {
+            (interface.position, field_anchors(&interface.fields, anchors))
+        }
+        Definition::TypeExtension(TypeExtension::InputObject(obj)) => {
+            (obj.position, input_value_anchors(&obj.fields, anchors))
+        }
+        Definition::TypeExtension(TypeExtension::Enum(enum_)) => {
+            (enum_.position, enum_value_anchors(&enum_.values, anchors))
+        }
+        Definition::TypeExtension(TypeExtension::Scalar(scalar)) => (scalar.position, false),
+        Definition::TypeExtension(TypeExtension::Union(union)) => {
+            let members = trivia.union_members(union.position, &union.types);
+            anchors.extend(&members);
+            positions.insert(union.position, members);
+            (union.position, false)
+        }
+        Definition::DirectiveDefinition(directive) => {
+            input_value_anchors(&directive.arguments, anchors);
+            (directive.position, false)
+        }
+    };
+
+    anchors.push(position);
+    if has_body {
+        blocks.extend(trivia.body_after(position));
+    }
 }
 
-fn format_def(def: Definition, indent: &mut Indentation, out: &mut Output) {
+fn field_anchors(fields: &[Field], anchors: &mut Vec<Pos>) -> bool {
+    for field in fields {
+        anchors.push(field.position);
+        input_value_anchors(&field.arguments, anchors);
+    }
+    !fields.is_empty()
+}
+
+fn input_value_anchors(values: &[InputValue], anchors: &mut Vec<Pos>) -> bool {
+    anchors.extend(values.iter().map(|value| value.position));
+    !values.is_empty()
+}
+
+fn enum_value_anchors(values: &[EnumValue], anchors: &mut Vec<Pos>) -> bool {
+    anchors.extend(values.iter().map(|value| value.position));
+    !values.is_empty()
+}
+
+fn format_def(def: Definition, cx: &mut Context) -> Doc {
     match def {
         Definition::SchemaDefinition(schema_def) => {
-            // TODO: directives
-
-            out.push("schema {\n", indent);
-            indent.increment();
-            if let Some(mutation) = schema_def.mutation {
-                out.push(&format!("mutation: {}\n", mutation), indent);
-            }
-            if let Some(query) = schema_def.query {
-                out.push(&format!("query: {}\n", query), indent);
-            }
-            if let Some(subscription) = schema_def.subscription {
-                out.push(&format!("subscription: {}\n", subscription), indent);
-            }
-            indent.decrement();
-            out.push("}\n\n", indent);
+            let types = vec![
+                schema_def.mutation,
+                schema_def.query,
+                schema_def.subscription,
+            ];
+            let positions = cx.positions(schema_def.position, OPERATIONS.len());
+            let operations = OPERATIONS
+                .iter()
+                .zip(types)
+                .zip(positions)
+                .filter_map(|((operation, type_), pos)| {
+                    let type_ = type_?;
+                    Some(cx.with_comments(pos, text(format!("{}: {}", operation, type_))))
+                })
+                .collect();
+
+            let doc = concat(vec![
+                text("schema"),
+                format_directives(&schema_def.directives, cx),
+                text(" "),
+                block(
+                    operations,
+                    hardline(),
+                    cx.dangling_comments(schema_def.position),
+                ),
+            ]);
+            cx.with_comments(schema_def.position, doc)
         }
 
-        Definition::TypeDefinition(type_def) => format_type(type_def, indent, out),
+        Definition::TypeDefinition(type_def) => format_type(type_def, cx),
 
-        Definition::TypeExtension(_) => unimplemented!("TypeExtension"),
+        Definition::TypeExtension(ext) => format_type_extension(ext, cx),
 
-        Definition::DirectiveDefinition(_) => unimplemented!("DirectiveDefinition"),
+        Definition::DirectiveDefinition(directive) => format_directive_definition(directive, cx),
     }
 }
 
-fn push_desc(desc: Option<String>, indent: &mut Indentation, out: &mut Output) {
-    if let Some(desc) = desc {
-        out.push(&format!("\"{}\"\n", desc), indent);
-    }
+/// A description, on the lines before the node it describes. Descriptions that span multiple
+/// lines or don't fit on one line become block strings, unless their contents would change by
+/// doing so.
+fn format_desc(desc: Option<String>) -> Doc {
+    let desc = match desc {
+        Some(desc) => desc,
+        None => return nil(),
+    };
+    let doc = if !is_block_string_safe(&desc) {
+        quoted_string(&desc)
+    } else if desc.contains('\n') {
+        block_string(&desc)
+    } else {
+        group(if_break(block_string(&desc), quoted_string(&desc)))
+    };
+    concat(vec![doc, hardline()])
 }
 
-fn format_type(type_def: TypeDefinition, indent: &mut Indentation, out: &mut Output) {
-    match type_def {
-        TypeDefinition::Object(obj) => {
-            // TODO: directives
+fn format_type(type_def: TypeDefinition, cx: &mut Context) -> Doc {
+    let (position, desc, doc) = match type_def {
+        TypeDefinition::Object(obj) => (
+            obj.position,
+            obj.description,
+            format_object(
+                "type",
+                obj.position,
+                &obj.name,
+                &obj.implements_interfaces,
+                &obj.directives,
+                obj.fields,
+                cx,
+            ),
+        ),
 
-            push_desc(obj.description, indent, out);
-            out.push(&format!("type {name}", name = obj.name), indent);
+        TypeDefinition::Enum(enum_) => (
+            enum_.position,
+            enum_.description,
+            format_enum(
+                "enum",
+                enum_.position,
+                &enum_.name,
+                &enum_.directives,
+                enum_.values,
+                cx,
+            ),
+        ),
 
-            if !obj.implements_interfaces.is_empty() {
-                out.push_str(" implements ");
-                map_join(obj.implements_interfaces.iter(), |name| name, " & ", out);
-            }
+        TypeDefinition::Scalar(scalar) => (
+            scalar.position,
+            scalar.description,
+            format_scalar("scalar", &scalar.name, &scalar.directives, cx),
+        ),
 
-            out.push_str(" {\n");
-            format_fields(obj.fields, indent, out);
-            out.push("}\n\n", indent);
-        }
+        TypeDefinition::Interface(interface) => (
+            interface.position,
+            interface.description,
+            format_object(
+                "interface",
+                interface.position,
+                &interface.name,
+                &[],
+                &interface.directives,
+                interface.fields,
+                cx,
+            ),
+        ),
 
-        TypeDefinition::Enum(enum_) => {
-            // TODO: directives
+        TypeDefinition::InputObject(obj) => (
+            obj.position,
+            obj.description,
+            format_input_object(
+                "input",
+                obj.position,
+                &obj.name,
+                &obj.directives,
+                obj.fields,
+                cx,
+            ),
+        ),
 
-            push_desc(enum_.description, indent, out);
-            out.push(&format!("enum {name} {{\n", name = enum_.name), indent);
+        TypeDefinition::Union(union) => (
+            union.position,
+            union.description,
+            format_union(
+                "union",
+                union.position,
+                &union.name,
+                &union.directives,
+                union.types,
+                cx,
+            ),
+        ),
+    };
 
-            indent.increment();
-            let mut values = enum_.values;
-            values.sort_unstable_by_key(|field| field.name.clone());
-            for value in values {
-                out.push(&format!("{name}\n", name = value.name), indent);
-            }
-            indent.decrement();
+    cx.with_description(position, format_desc(desc), doc)
+}
 
-            out.push("}\n\n", indent);
-        }
+fn format_type_extension(ext: TypeExtension, cx: &mut Context) -> Doc {
+    let position = match &ext {
+        TypeExtension::Object(obj) => obj.position,
+        TypeExtension::Enum(enum_) => enum_.position,
+        TypeExtension::Scalar(scalar) => scalar.position,
+        TypeExtension::Interface(interface) => interface.position,
+        TypeExtension::InputObject(obj) => obj.position,
+        TypeExtension::Union(union) => union.position,
+    };
+
+    let doc = match ext {
+        TypeExtension::Object(obj) => format_object(
+            "extend type",
+            position,
+            &obj.name,
+            &obj.implements_interfaces,
+            &obj.directives,
+            obj.fields,
+            cx,
+        ),
 
-        TypeDefinition::Scalar(scalar) => {
-            // TODO: directives
+        TypeExtension::Enum(enum_) => format_enum(
+            "extend enum",
+            position,
+            &enum_.name,
+            &enum_.directives,
+            enum_.values,
+            cx,
+        ),
 
-            push_desc(scalar.description, indent, out);
-            out.push(&format!("scalar {name}\n\n", name = scalar.name), indent);
+        TypeExtension::Scalar(scalar) => {
+            format_scalar("extend scalar", &scalar.name, &scalar.directives, cx)
         }
 
-        TypeDefinition::Interface(interface) => {
-            // TODO: directives
+        TypeExtension::Interface(interface) => format_object(
+            "extend interface",
+            position,
+            &interface.name,
+            &[],
+            &interface.directives,
+            interface.fields,
+            cx,
+        ),
 
-            push_desc(interface.description, indent, out);
-            out.push(
-                &format!("interface {name} {{\n", name = interface.name),
-                indent,
-            );
-            format_fields(interface.fields, indent, out);
-            out.push("}\n\n", indent);
-        }
+        TypeExtension::InputObject(obj) => format_input_object(
+            "extend input",
+            position,
+            &obj.name,
+            &obj.directives,
+            obj.fields,
+            cx,
+        ),
 
-        TypeDefinition::InputObject(obj) => {
-            // TODO: directives
+        TypeExtension::Union(union) => format_union(
+            "extend union",
+            union.position,
+            &union.name,
+            &union.directives,
+            union.types,
+            cx,
+        ),
+    };
 
-            push_desc(obj.description, indent, out);
-            out.push(&format!("input {name} {{\n", name = obj.name), indent);
-            format_input_values(obj.fields, indent, out);
-            out.push("}\n\n", indent);
-        }
+    cx.with_comments(position, doc)
+}
 
-        TypeDefinition::Union(union) => {
-            // TODO: directives
+fn format_object(
+    keyword: &str,
+    position: Pos,
+    name: &str,
+    interfaces: &[NamedType],
+    directives: &[Directive],
+    fields: Vec<Field>,
+    cx: &mut Context,
+) -> Doc {
+    let mut docs = vec![text(format!(
+        "{keyword} {name}",
+        keyword = keyword,
+        name = name
+    ))];
+
+    let brace = if fields.is_empty() { nil() } else { text(" ") };
+    if interfaces.is_empty() {
+        docs.push(format_directives(directives, cx));
+        docs.push(brace);
+    } else {
+        // When the interfaces break, the directives and the brace go on a line of their own,
+        // so that they don't look like they belong to the last interface.
+        let directives_line = if directives.is_empty() {
+            line()
+        } else {
+            let directives = directives
+                .iter()
+                .map(|directive| format_directive(directive, cx))
+                .collect();
+            concat(vec![line(), join(directives, text(" ")), brace.clone()])
+        };
+        let tail = if_break(
+            directives_line,
+            concat(vec![format_directives(directives, cx), brace]),
+        );
 
-            push_desc(union.description, indent, out);
-            out.push(&format!("union {name} = ", name = union.name), indent);
+        let interfaces = interfaces
+            .iter()
+            .map(|name| (Pos::default(), name))
+            .collect::<Vec<_>>();
+        docs.push(format_type_list(" implements", &interfaces, "&", tail, cx));
+    }
 
-            let mut types = union.types;
-            types.sort_unstable_by_key(|type_| type_.clone());
-            map_join(types.iter(), |type_| type_, " | ", out);
-            out.push_str("\n\n");
-        }
+    if !fields.is_empty() {
+        let fields = format_fields(fields, cx);
+        docs.push(block(fields, hardline(), cx.dangling_comments(position)));
     }
+    concat(docs)
 }
 
-fn format_fields(fields: Vec<Field>, indent: &mut Indentation, out: &mut Output) {
-    indent.increment();
+fn format_enum(
+    keyword: &str,
+    position: Pos,
+    name: &str,
+    directives: &[Directive],
+    values: Vec<EnumValue>,
+    cx: &mut Context,
+) -> Doc {
+    let mut docs = vec![
+        text(format!("{keyword} {name}", keyword = keyword, name = name)),
+        format_directives(directives, cx),
+    ];
+
+    if !values.is_empty() {
+        let has_docs = values.iter().any(|value| value.description.is_some());
+        let values = format_enum_values(values, cx);
+        docs.push(text(" "));
+        docs.push(block(
+            values,
+            item_separator(has_docs),
+            cx.dangling_comments(position),
+        ));
+    }
+    concat(docs)
+}
 
-    let mut fields = fields.clone();
-    fields.sort_unstable_by_key(|field| field.name.clone());
+/// Like documented input values, documented enum values are separated by blank lines.
+fn item_separator(has_docs: bool) -> Doc {
+    if has_docs {
+        blank_line()
+    } else {
+        hardline()
+    }
+}
 
-    for field in fields {
-        format_field(field, indent, out);
+fn format_enum_values(values: Vec<EnumValue>, cx: &mut Context) -> Vec<Doc> {
+    let mut values = values;
+    if cx.config.sort.enum_values {
+        values.sort_by_key(|value| value.name.clone());
     }
 
-    indent.decrement();
+    values
+        .into_iter()
+        .map(|value| {
+            let doc = concat(vec![
+                text(value.name),
+                format_directives(&value.directives, cx),
+            ]);
+            cx.with_description(value.position, format_desc(value.description), doc)
+        })
+        .collect()
 }
 
-fn format_field(field: Field, indent: &mut Indentation, out: &mut Output) {
-    // TODO: directives
+fn format_scalar(keyword: &str, name: &str, directives: &[Directive], cx: &mut Context) -> Doc {
+    concat(vec![
+        text(format!("{keyword} {name}", keyword = keyword, name = name)),
+        format_directives(directives, cx),
+    ])
+}
 
-    push_desc(field.description, indent, out);
-    out.push(&field.name, indent);
+fn format_input_object(
+    keyword: &str,
+    position: Pos,
+    name: &str,
+    directives: &[Directive],
+    fields: Vec<InputValue>,
+    cx: &mut Context,
+) -> Doc {
+    let mut docs = vec![
+        text(format!("{keyword} {name}", keyword = keyword, name = name)),
+        format_directives(directives, cx),
+    ];
+
+    if !fields.is_empty() {
+        let has_docs = fields.iter().any(|field| field.description.is_some());
+        let fields = format_input_values(fields, cx);
+        docs.push(text(" "));
+        docs.push(block(
+            fields,
+            item_separator(has_docs),
+            cx.dangling_comments(position),
+        ));
+    }
+    concat(docs)
+}
 
-    if !field.arguments.is_empty() {
-        out.push_str("(");
-        let current_line_length = out.current_line_length();
-
-        let mut args = field
-            .arguments
-            .into_iter()
-            .map(|input_value| {
-                let mut out = Output::new();
-                let mut indent = Indentation::new(0);
-                format_input_value(input_value, &mut indent, &mut out);
-                out.trim().to_string()
-            })
-            .collect::<Vec<_>>();
-        args.sort_unstable();
-        let args_joined = args.join(", ") + ")";
-
-        let line_length_with_args = current_line_length + args_joined.len();
-
-        if line_length_with_args > MAX_LINE_LENGTH {
-            indent.increment();
-            out.push_str("\n");
-            args.iter().for_each(|arg| {
-                out.push(&format!("{},\n", arg), indent);
-            });
-            indent.decrement();
-            out.push(")", indent);
-        } else {
-            out.push_str(&args_joined);
+fn format_union(
+    keyword: &str,
+    position: Pos,
+    name: &str,
+    directives: &[Directive],
+    types: Vec<NamedType>,
+    cx: &mut Context,
+) -> Doc {
+    let mut docs = vec![
+        text(format!("{keyword} {name}", keyword = keyword, name = name)),
+        format_directives(directives, cx),
+    ];
+
+    if !types.is_empty() {
+        let positions = cx.positions(position, types.len());
+        let mut types = positions.into_iter().zip(types).collect::<Vec<_>>();
+        if cx.config.sort.union_members {
+            types.sort_by(|(_, a), (_, b)| a.cmp(b));
         }
+        docs.push(format_type_list(" =", &types, "|", nil(), cx));
     }
+    concat(docs)
+}
 
-    out.push_str(&format!(": {type_}\n", type_ = field.field_type));
+/// A keyword followed by a list of names, like the members of a union, with the positions of
+/// the names for their comments. If the list doesn't fit on the current line, each name goes
+/// on its own line after a leading separator. The `tail` is printed after the list, in the
+/// same group, so that it can choose its layout with `if_break`.
+fn format_type_list<T: AsRef<str>>(
+    keyword: &str,
+    names: &[(Pos, T)],
+    separator: &str,
+    tail: Doc,
+    cx: &mut Context,
+) -> Doc {
+    let separator = format!("{} ", separator);
+    let names = names
+        .iter()
+        .enumerate()
+        .map(|(i, (pos, name))| {
+            let separator = if i == 0 {
+                if_break(text(separator.clone()), nil())
+            } else {
+                text(separator.clone())
+            };
+            cx.with_comments(*pos, concat(vec![separator, text(name.as_ref())]))
+        })
+        .collect();
+
+    concat(vec![
+        text(keyword),
+        group(concat(vec![
+            nest(concat(vec![line(), join(names, line())])),
+            tail,
+        ])),
+    ])
 }
 
-fn format_input_values(values: Vec<InputValue>, indent: &mut Indentation, out: &mut Output) {
-    indent.increment();
+fn format_directive_definition(directive: DirectiveDefinition, cx: &mut Context) -> Doc {
+    let mut docs = vec![text(format!("directive @{name}", name = directive.name))];
 
-    let mut values = values.clone();
-    values.sort_unstable_by_key(|field| field.name.clone());
+    if !directive.arguments.is_empty() {
+        docs.push(format_arguments(directive.arguments, cx));
+    }
 
-    let has_docs = values.iter().any(|value| value.description.is_some());
-    let no_docs = values.iter().all(|value| value.description.is_none());
-    assert!(!(has_docs && no_docs));
+    let locations = directive
+        .locations
+        .iter()
+        .map(|location| (Pos::default(), location.as_str()))
+        .collect::<Vec<_>>();
+    docs.push(format_type_list(" on", &locations, "|", nil(), cx));
 
-    for pos in values.into_iter().with_position() {
-        use itertools::Position::*;
+    let desc = format_desc(directive.description);
+    cx.with_description(directive.position, desc, concat(docs))
+}
 
-        let value = pos.clone().into_inner();
-        format_input_value(value, indent, out);
+fn format_fields(fields: Vec<Field>, cx: &mut Context) -> Vec<Doc> {
+    let mut fields = fields;
+    if cx.config.sort.fields {
+        fields.sort_by_key(|field| field.name.clone());
+    }
 
-        let push_newline_because_docs = match pos {
-            First(_) | Middle(_) if has_docs => true,
-            Last(_) | Only(_) | _ => false,
-        };
+    fields
+        .into_iter()
+        .map(|field| format_field(field, cx))
+        .collect()
+}
 
-        if push_newline_because_docs {
-            out.push_str("\n\n");
-        } else if no_docs {
-            out.push_str("\n");
-        }
+fn format_field(field: Field, cx: &mut Context) -> Doc {
+    let mut docs = vec![text(field.name)];
 
-        match pos {
-            Last(_) if !no_docs => out.push_str("\n"),
-            _ => {}
-        };
+    if !field.arguments.is_empty() {
+        docs.push(format_arguments(field.arguments, cx));
     }
 
-    indent.decrement();
+    docs.push(text(format!(": {type_}", type_ = field.field_type)));
+    docs.push(format_directives(&field.directives, cx));
+
+    let desc = format_desc(field.description);
+    cx.with_description(field.position, desc, concat(docs))
 }
 
-fn format_input_value(value: InputValue, indent: &mut Indentation, out: &mut Output) {
-    // TODO: default value
-    // TODO: directives
+fn format_arguments(arguments: Vec<InputValue>, cx: &mut Context) -> Doc {
+    let args = arguments
+        .into_iter()
+        .map(|input_value| {
+            let name = input_value.name.clone();
+            (name, format_input_value(input_value, cx))
+        })
+        .collect();
+    argument_list(args, nil(), cx)
+}
 
-    push_desc(value.description.clone(), indent, out);
+fn format_input_values(values: Vec<InputValue>, cx: &mut Context) -> Vec<Doc> {
+    let mut values = values;
+    if cx.config.sort.fields {
+        values.sort_by_key(|value| value.name.clone());
+    }
 
-    out.push(
-        &format!(
-            "{name}: {type_}",
-            name = value.name,
-            type_ = value.value_type
-        ),
-        indent,
-    );
+    values
+        .into_iter()
+        .map(|value| format_input_value(value, cx))
+        .collect()
+}
+
+fn format_input_value(value: InputValue, cx: &mut Context) -> Doc {
+    let mut docs = vec![text(format!(
+        "{name}: {type_}",
+        name = value.name,
+        type_ = value.value_type
+    ))];
+
+    if let Some(default) = &value.default_value {
+        docs.push(text(" = "));
+        docs.push(format_value(default, cx));
+    }
+
+    docs.push(format_directives(&value.directives, cx));
+
+    let desc = format_desc(value.description);
+    cx.with_description(value.position, desc, concat(docs))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::format::config::SortKind;
     #[allow(unused_imports)]
-    use crate::format::format_test;
+    use crate::format::{format_test, format_test_with_config};
 
     #[test]
     fn test_basic() {
@@ -458,5 +842,548 @@ input WithoutDocs {
         );
     }
 
+    #[test]
+    fn test_directives() {
+        format_test(
+            format,
+            "
+schema @a { query: Query }
+type User implements Node @key(fields: \"id\") { id: ID! @external name(upper: Boolean @b): String @deprecated }
+enum Role @c { ADMIN }
+scalar Date @specifiedBy(url: \"https://example.com\")
+interface Node @d { id: ID! }
+input UserInput @e { id: ID! @f }
+union Result @g = User | Team
+            ",
+            "
+schema @a {
+  query: Query
+}
+
+type User implements Node @key(fields: \"id\") {
+  id: ID! @external
+  name(upper: Boolean @b): String @deprecated
+}
+
+enum Role @c {
+  ADMIN
+}
+
+scalar Date @specifiedBy(url: \"https://example.com\")
+
+interface Node @d {
+  id: ID!
+}
+
+input UserInput @e {
+  id: ID! @f
+}
+
+union Result @g = Team | User
+            ",
+        );
+    }
+
+    #[test]
+    fn test_directives_long_lines() {
+        format_test(
+            format,
+            "
+type Query {
+  user: User @deprecated(reason: \"Use the `viewer` field instead, it has more information\")
+}
+            ",
+            "
+type Query {
+  user: User @deprecated(
+    reason: \"Use the `viewer` field instead, it has more information\",
+  )
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_type_extensions() {
+        format_test(
+            format,
+            "
+extend type Query implements Node @key(fields: \"id\") { users: [User] id: ID! }
+extend type Mutation { deleteUser(id: ID!): User createUser(name: String): User }
+extend interface Node @a { slug: String }
+extend union SearchResult = Team | Droid
+extend union Other @b
+extend enum Role { OWNER ADMIN }
+extend input UserInput { slug: String id: ID }
+extend scalar Date @c
+            ",
+            "
+extend type Query implements Node @key(fields: \"id\") {
+  id: ID!
+  users: [User]
+}
+
+extend type Mutation {
+  createUser(name: String): User
+  deleteUser(id: ID!): User
+}
+
+extend interface Node @a {
+  slug: String
+}
+
+extend union SearchResult = Droid | Team
+
+extend union Other @b
+
+extend enum Role {
+  ADMIN
+  OWNER
+}
+
+extend input UserInput {
+  id: ID
+  slug: String
+}
+
+extend scalar Date @c
+            ",
+        );
+    }
+
+    #[test]
+    fn test_directive_definitions() {
+        format_test(
+            format,
+            "
+\"Marks an element as no longer supported\"
+directive @deprecated(reason: String = \"No longer supported\") on FIELD_DEFINITION | ENUM_VALUE
+directive @live on QUERY
+directive @auth(requires: Role = ADMIN, scopes: [String!] = [\"read\"]) on OBJECT | FIELD_DEFINITION | INTERFACE
+            ",
+            "
+\"Marks an element as no longer supported\"
+directive @deprecated(reason: String = \"No longer supported\") on
+  | FIELD_DEFINITION
+  | ENUM_VALUE
+
+directive @live on QUERY
+
+directive @auth(requires: Role = ADMIN, scopes: [String!] = [\"read\"]) on
+  | OBJECT
+  | FIELD_DEFINITION
+  | INTERFACE
+            ",
+        );
+    }
+
+    #[test]
+    fn test_directive_definitions_long_arguments() {
+        format_test(
+            format,
+            "
+directive @cost(complexity: Int = 1, multipliers: [String!], useMultipliers: Boolean = true) on FIELD
+            ",
+            "
+directive @cost(
+  complexity: Int = 1,
+  multipliers: [String!],
+  useMultipliers: Boolean = true,
+) on FIELD
+            ",
+        );
+    }
+
+    #[test]
+    fn test_default_values() {
+        format_test(
+            format,
+            "
+input UserFilter { first: Int = 10 role: Role = ADMIN tags: [String!] = [\"a\", \"b\"] }
+type Query { users(first: Int = 10, filter: UserFilter = {first: 5}): [User] }
+            ",
+            "
+input UserFilter {
+  first: Int = 10
+  role: Role = ADMIN
+  tags: [String!] = [\"a\", \"b\"]
+}
+
+type Query {
+  users(filter: UserFilter = {first: 5}, first: Int = 10): [User]
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_nested_default_values_long_lines() {
+        format_test(
+            format,
+            "
+input Search {
+  filter: Filter = {and: [{name: \"something long\", role: ADMIN}, {name: \"something else\", role: USER}]}
+}
+type Query {
+  search(filter: Filter = {and: [{name: \"something long\"}, {name: \"something else\"}], limit: 10}): [User]
+}
+            ",
+            "
+input Search {
+  filter: Filter = {
+    and: [
+      {name: \"something long\", role: ADMIN},
+      {name: \"something else\", role: USER},
+    ],
+  }
+}
+
+type Query {
+  search(
+    filter: Filter = {
+      and: [{name: \"something long\"}, {name: \"something else\"}],
+      limit: 10,
+    },
+  ): [User]
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        format_test(
+            format,
+            "
+# Users
+type User { # trailing
+  # The name
+  name: String
+  id: ID! # sorted first
+  friends(
+    # How many
+    first: Int
+  ): [User]
+  # dangling
+}
+
+enum Role {
+  USER
+  # The boss
+  ADMIN
+}
+# the end
+            ",
+            "
+# Users
+type User { # trailing
+  friends(
+    # How many
+    first: Int,
+  ): [User]
+  id: ID! # sorted first
+  # The name
+  name: String
+  # dangling
+}
+
+enum Role {
+  # The boss
+  ADMIN
+  USER
+}
+
+# the end
+            ",
+        );
+    }
+
+    #[test]
+    fn test_comments_in_union_members() {
+        format_test(
+            format,
+            "
+union Actor =
+  # People
+  | User
+  # Scripts
+  | Bot # sorted first
+type Bot { id: ID! }
+            ",
+            "
+union Actor =
+  # Scripts
+  | Bot # sorted first
+  # People
+  | User
+
+type Bot {
+  id: ID!
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_comments_in_schema_definition() {
+        format_test(
+            format,
+            "
+schema { # trailing
+  # Reads
+  query: Query
+  # Writes
+  mutation: Mutation # sorted first
+}
+            ",
+            "
+schema { # trailing
+  # Writes
+  mutation: Mutation # sorted first
+  # Reads
+  query: Query
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_comments_after_block_strings() {
+        format_test(
+            format,
+            "
+type User {
+  \"The\\nname\" name( # trailing the field
+    format: String = \"first\\nlast\" # trailing the argument
+  ): String
+}
+            ",
+            "
+type User {
+  \"\"\"
+  The
+  name
+  \"\"\"
+  name( # trailing the field
+    format: String = \"\"\"
+    first
+    last
+    \"\"\", # trailing the argument
+  ): String
+}
+            ",
+        );
+    }
+
+    #[test]
+    fn test_preserve_order() {
+        let mut config = Config::default();
+        config.sort.preserve(SortKind::Fields);
+        config.sort.preserve(SortKind::EnumValues);
+        config.sort.preserve(SortKind::UnionMembers);
+
+        format_test_with_config(
+            format,
+            &config,
+            "
+type User { name: String id(b: Int, a: Int): ID! }
+input UserInput { name: String id: ID! }
+enum Role { USER ADMIN }
+union Actor = User | Bot
+            ",
+            "
+type User {
+  name: String
+  id(a: Int, b: Int): ID!
+}
+
+input UserInput {
+  name: String
+  id: ID!
+}
+
+enum Role {
+  USER
+  ADMIN
+}
+
+union Actor = User | Bot
+            ",
+        );
+    }
+
+    #[test]
+    fn test_block_string_descriptions() {
+        format_test(
+            format,
+            r#"
+"""
+The user type.
+
+    Indented example
+"""
+type User {
+  """A block string on one line"""
+  id: ID!
+  "A description that is much too long to fit on a single line, so it becomes a block"
+  name: String
+  "Quotes \" and \\ backslashes"
+  slug(
+    """
+    The format
+    of the slug
+    """
+    format: String
+  ): String
+}
+
+enum Role {
+  """
+  Can do anything
+  """
+  ADMIN
+}
+
+"""
+Caches a field
+"""
+directive @cached on FIELD_DEFINITION
+            "#,
+            r#"
+"""
+The user type.
+
+    Indented example
+"""
+type User {
+  "A block string on one line"
+  id: ID!
+  """
+  A description that is much too long to fit on a single line, so it becomes a block
+  """
+  name: String
+  "Quotes \" and \\ backslashes"
+  slug(
+    """
+    The format
+    of the slug
+    """
+    format: String,
+  ): String
+}
+
+enum Role {
+  "Can do anything"
+  ADMIN
+}
+
+"Caches a field"
+directive @cached on FIELD_DEFINITION
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_descriptions_not_safe_in_block_strings() {
+        format_test(
+            format,
+            r#"
+type User {
+  "  only indented lines\n  like these"
+  id: ID!
+}
+            "#,
+            r#"
+type User {
+  "  only indented lines\n  like these"
+  id: ID!
+}
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_enum_values() {
+        format_test(
+            format,
+            r#"
+enum Role {
+  USER
+  "Not used anymore"
+  GUEST @deprecated(reason: "Use USER")
+  """
+  Can do anything
+  """
+  ADMIN
+}
+
+enum Size { SMALL LARGE @deprecated }
+            "#,
+            r#"
+enum Role {
+  "Can do anything"
+  ADMIN
+
+  "Not used anymore"
+  GUEST @deprecated(reason: "Use USER")
+
+  USER
+}
+
+enum Size {
+  LARGE @deprecated
+  SMALL
+}
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_long_type_lists() {
+        format_test(
+            format,
+            "
+type SearchResultConnection implements Node & Connection & Paginated & Timestamped @key(fields: \"id\") {
+  id: ID!
+}
+
+type SearchResultEdge implements Node & Edge & Paginated & Timestamped & Versioned & Cached {
+  id: ID!
+}
+
+union SearchResult = Photo | Person | Organization | Repository | Discussion | Comment
+            ",
+            "
+type SearchResultConnection implements
+  & Node
+  & Connection
+  & Paginated
+  & Timestamped
+@key(fields: \"id\") {
+  id: ID!
+}
+
+type SearchResultEdge implements
+  & Node
+  & Edge
+  & Paginated
+  & Timestamped
+  & Versioned
+  & Cached
+{
+  id: ID!
+}
+
+union SearchResult =
+  | Comment
+  | Discussion
+  | Organization
+  | Person
+  | Photo
+  | Repository
+            ",
+        );
+    }
+
     // TODO: args with docs
 }
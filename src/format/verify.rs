@@ -0,0 +1,720 @@
+//! A safety net for the formatters: the formatted output is parsed again and compared with
+//! the document it came from, so that a bug in a formatter can't silently change what a query
+//! or schema means.
+//!
+//! The documents are compared after undoing the sorting the formatters do on purpose.
+//! Positions are cleared, and comments aren't part of the AST. Both documents are parsed with
+//! the block string values of the spec, so a string printed as a block string must have the
+//! value of the original string.
+
+use super::config::Sort;
+use super::parse::{parse_query, parse_schema};
+use super::query::selection_set_sort_key;
+use crate::ast::{query, schema};
+use failure::{bail, Error};
+use graphql_parser::Pos;
+use std::fmt;
+
+pub fn verify_query(original: query::Document, formatted: &str, sort: &Sort) -> Result<(), Error> {
+    let output = match parse_query(formatted) {
+        Ok(output) => output,
+        Err(err) => bail!(invalid_output(&err.to_string())),
+    };
+
+    compare(
+        normalize_query(original, sort),
+        normalize_query(output, sort),
+    )
+}
+
+pub fn verify_schema(
+    original: schema::Document,
+    formatted: &str,
+    sort: &Sort,
+) -> Result<(), Error> {
+    let output = match parse_schema(formatted) {
+        Ok(output) => output,
+        Err(err) => bail!(invalid_output(&err.to_string())),
+    };
+
+    compare(
+        normalize_schema(original, sort),
+        normalize_schema(output, sort),
+    )
+}
+
+fn invalid_output(err: &str) -> String {
+    format!(
+        "the formatted output doesn't parse, which is a bug in the formatter: {}",
+        err.trim()
+    )
+}
+
+/// Compare the normalized documents, showing the lines of their printed forms that differ.
+/// Values like `1.0` and `1` print the same, so if there is no difference in print, the debug
+/// forms are shown instead.
+fn compare<T: PartialEq + fmt::Display + fmt::Debug>(expected: T, actual: T) -> Result<(), Error> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    let mut changes = diff_lines(&expected.to_string(), &actual.to_string());
+    if changes.is_empty() {
+        changes = diff_lines(&format!("{:#?}", expected), &format!("{:#?}", actual));
+    }
+
+    bail!(
+        "formatting would change the document, which is a bug in the formatter:\n{}",
+        changes.join("\n")
+    )
+}
+
+fn diff_lines(expected: &str, actual: &str) -> Vec<String> {
+    diff::lines(expected, actual)
+        .into_iter()
+        .filter_map(|line| match line {
+            diff::Result::Left(line) => Some(format!("-{}", line)),
+            diff::Result::Right(line) => Some(format!("+{}", line)),
+            diff::Result::Both(..) => None,
+        })
+        .collect()
+}
+
+/// Sort the nodes of `doc` like a formatter with the `sort` settings does, and clear their
+/// positions.
+pub fn normalize_query(mut doc: query::Document, sort: &Sort) -> query::Document {
+    use crate::ast::query::*;
+
+    for def in &mut doc.definitions {
+        match def {
+            Definition::Operation(OperationDefinition::Query(query)) => {
+                query.position = Pos::default();
+                normalize_variable_definitions(&mut query.variable_definitions);
+                normalize_directives(&mut query.directives, sort);
+                normalize_selection_set(&mut query.selection_set, sort);
+            }
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+                mutation.position = Pos::default();
+                normalize_variable_definitions(&mut mutation.variable_definitions);
+                normalize_directives(&mut mutation.directives, sort);
+                normalize_selection_set(&mut mutation.selection_set, sort);
+            }
+            Definition::Operation(OperationDefinition::Subscription(sub)) => {
+                sub.position = Pos::default();
+                normalize_variable_definitions(&mut sub.variable_definitions);
+                normalize_directives(&mut sub.directives, sort);
+                normalize_selection_set(&mut sub.selection_set, sort);
+            }
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => {
+                normalize_selection_set(set, sort)
+            }
+            Definition::Fragment(frag) => {
+                frag.position = Pos::default();
+                normalize_directives(&mut frag.directives, sort);
+                normalize_selection_set(&mut frag.selection_set, sort);
+            }
+        }
+    }
+
+    doc
+}
+
+fn normalize_variable_definitions(vars: &mut [query::VariableDefinition]) {
+    for var in vars {
+        var.position = Pos::default();
+    }
+}
+
+fn normalize_selection_set(set: &mut query::SelectionSet, sort: &Sort) {
+    use crate::ast::query::*;
+
+    set.span = (Pos::default(), Pos::default());
+    if sort.selections {
+        set.items.sort_by_key(selection_set_sort_key);
+    }
+
+    for selection in &mut set.items {
+        match selection {
+            Selection::Field(field) => {
+                field.position = Pos::default();
+                normalize_arguments(&mut field.arguments, sort);
+                normalize_directives(&mut field.directives, sort);
+                normalize_selection_set(&mut field.selection_set, sort);
+            }
+            Selection::FragmentSpread(frag_spread) => {
+                frag_spread.position = Pos::default();
+                normalize_directives(&mut frag_spread.directives, sort)
+            }
+            Selection::InlineFragment(inline_frag) => {
+                inline_frag.position = Pos::default();
+                normalize_directives(&mut inline_frag.directives, sort);
+                normalize_selection_set(&mut inline_frag.selection_set, sort);
+            }
+        }
+    }
+}
+
+fn normalize_arguments(arguments: &mut [(String, query::Value)], sort: &Sort) {
+    if sort.arguments {
+        arguments.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+}
+
+fn normalize_directives(directives: &mut [query::Directive], sort: &Sort) {
+    for directive in directives {
+        directive.position = Pos::default();
+        normalize_arguments(&mut directive.arguments, sort);
+    }
+}
+
+fn normalize_schema(mut doc: schema::Document, sort: &Sort) -> schema::Document {
+    use crate::ast::schema::*;
+
+    for def in &mut doc.definitions {
+        match def {
+            Definition::SchemaDefinition(schema_def) => {
+                schema_def.position = Pos::default();
+                normalize_directives(&mut schema_def.directives, sort)
+            }
+
+            Definition::TypeDefinition(TypeDefinition::Object(obj)) => {
+                obj.position = Pos::default();
+                normalize_directives(&mut obj.directives, sort);
+                normalize_fields(&mut obj.fields, sort);
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                interface.position = Pos::default();
+                normalize_directives(&mut interface.directives, sort);
+                normalize_fields(&mut interface.fields, sort);
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(obj)) => {
+                obj.position = Pos::default();
+                normalize_directives(&mut obj.directives, sort);
+                normalize_input_values(&mut obj.fields, sort.fields, sort);
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(enum_)) => {
+                enum_.position = Pos::default();
+                normalize_directives(&mut enum_.directives, sort);
+                normalize_enum_values(&mut enum_.values, sort);
+            }
+            Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                scalar.position = Pos::default();
+                normalize_directives(&mut scalar.directives, sort);
+            }
+            Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                union.position = Pos::default();
+                normalize_directives(&mut union.directives, sort);
+                if sort.union_members {
+                    union.types.sort();
+                }
+            }
+
+            Definition::TypeExtension(TypeExtension::Object(obj)) => {
+                obj.position = Pos::default();
+                normalize_directives(&mut obj.directives, sort);
+                normalize_fields(&mut obj.fields, sort);
+            }
+            Definition::TypeExtension(TypeExtension::Interface(interface)) => {
+                interface.position = Pos::default();
+                normalize_directives(&mut interface.directives, sort);
+                normalize_fields(&mut interface.fields, sort);
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(obj)) => {
+                obj.position = Pos::default();
+                normalize_directives(&mut obj.directives, sort);
+                normalize_input_values(&mut obj.fields, sort.fields, sort);
+            }
+            Definition::TypeExtension(TypeExtension::Enum(enum_)) => {
+                enum_.position = Pos::default();
+                normalize_directives(&mut enum_.directives, sort);
+                normalize_enum_values(&mut enum_.values, sort);
+            }
+            Definition::TypeExtension(TypeExtension::Scalar(scalar)) => {
+                scalar.position = Pos::default();
+                normalize_directives(&mut scalar.directives, sort)
+            }
+            Definition::TypeExtension(TypeExtension::Union(union)) => {
+                union.position = Pos::default();
+                normalize_directives(&mut union.directives, sort);
+                if sort.union_members {
+                    union.types.sort();
+                }
+            }
+
+            Definition::DirectiveDefinition(directive) => {
+                directive.position = Pos::default();
+                normalize_input_values(&mut directive.arguments, sort.arguments, sort);
+            }
+        }
+    }
+
+    doc
+}
+
+fn normalize_fields(fields: &mut Vec<schema::Field>, sort: &Sort) {
+    if sort.fields {
+        fields.sort_by_key(|field| field.name.clone());
+    }
+
+    for field in fields {
+        field.position = Pos::default();
+        normalize_input_values(&mut field.arguments, sort.arguments, sort);
+        normalize_directives(&mut field.directives, sort);
+    }
+}
+
+/// Normalize input values, which are sorted like arguments or like fields depending on where
+/// they are.
+fn normalize_input_values(values: &mut Vec<schema::InputValue>, sorted: bool, sort: &Sort) {
+    if sorted {
+        values.sort_by_key(|value| value.name.clone());
+    }
+
+    for value in values {
+        value.position = Pos::default();
+        normalize_directives(&mut value.directives, sort);
+    }
+}
+
+fn normalize_enum_values(values: &mut Vec<schema::EnumValue>, sort: &Sort) {
+    if sort.enum_values {
+        values.sort_by_key(|value| value.name.clone());
+    }
+
+    for value in values {
+        value.position = Pos::default();
+        normalize_directives(&mut value.directives, sort);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::config::Config;
+    use crate::format::minify::minify;
+    use crate::format::{query, schema};
+    use quickcheck::{Arbitrary, Gen, QuickCheck, TestResult};
+
+    #[test]
+    fn detects_changes() {
+        let sort = Sort::default();
+        let original = parse_query("{ user(id: 1) { name } }").unwrap();
+
+        assert!(verify_query(original.clone(), "{ user(id: 1) { name } }", &sort).is_ok());
+        assert!(verify_query(original.clone(), "{ user(id: 2) { name } }", &sort).is_err());
+        assert!(verify_query(original, "{ user(id: 1) { name }", &sort).is_err());
+    }
+
+    #[test]
+    fn allows_sorting_and_descriptions() {
+        let original = parse_schema("\"\"\"\nA user\n\"\"\"\ntype User { name: String id: ID }");
+
+        assert!(verify_schema(
+            original.unwrap(),
+            "\"A user\"\ntype User { id: ID name: String }",
+            &Sort::default()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn compares_block_string_values() {
+        let sort = Sort::default();
+        let block_string = "{ a(s: \"\"\"\n  one\n  two\n\"\"\") }";
+
+        let original = parse_query("{ a(s: \"one\\ntwo\\n\") }").unwrap();
+        assert!(verify_query(original, block_string, &sort).is_err());
+
+        let original = parse_query("{ a(s: \"one\\ntwo\") }").unwrap();
+        assert!(verify_query(original, block_string, &sort).is_ok());
+    }
+
+    #[test]
+    fn respects_preserved_order() {
+        let sort = Sort {
+            fields: false,
+            ..Sort::default()
+        };
+        let original = parse_schema("type User { name: String id: ID }").unwrap();
+
+        assert!(verify_schema(original, "type User { id: ID name: String }", &sort).is_err());
+    }
+
+    /// A random document, and the settings to format it with.
+    #[derive(Debug, Clone)]
+    struct Input {
+        source: String,
+        config: Config,
+    }
+
+    fn arbitrary_config(g: &mut Gen) -> Config {
+        let mut config = Config {
+            line_width: *g.choose(&[20, 40, 80]).unwrap(),
+            indent_size: *g.choose(&[2, 4]).unwrap(),
+            trailing_commas: bool::arbitrary(g),
+            ..Config::default()
+        };
+        if bool::arbitrary(g) {
+            config.sort.preserve(crate::format::config::SortKind::All);
+        }
+        config
+    }
+
+    fn pick(g: &mut Gen, items: &[&str]) -> String {
+        g.choose(items).unwrap().to_string()
+    }
+
+    /// Up to `max` items made by `item`, joined by `separator`.
+    fn some<F>(g: &mut Gen, min: usize, max: usize, separator: &str, mut item: F) -> String
+    where
+        F: FnMut(&mut Gen) -> String,
+    {
+        let count = min + usize::arbitrary(g) % (max - min + 1);
+        (0..count)
+            .map(|_| item(g))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn maybe<F>(g: &mut Gen, item: F) -> String
+    where
+        F: FnOnce(&mut Gen) -> String,
+    {
+        if bool::arbitrary(g) {
+            item(g)
+        } else {
+            String::new()
+        }
+    }
+
+    fn name(g: &mut Gen) -> String {
+        pick(
+            g,
+            &["a", "b", "id", "name", "user", "team", "first", "node"],
+        )
+    }
+
+    fn type_name(g: &mut Gen) -> String {
+        pick(g, &["User", "Team", "Node", "Int", "String"])
+    }
+
+    fn comment(g: &mut Gen) -> String {
+        maybe(g, |g| format!("# {}\n", name(g)))
+    }
+
+    fn value(g: &mut Gen, depth: usize) -> String {
+        let scalar = pick(
+            g,
+            &[
+                "1",
+                "-12",
+                "1.5",
+                "2.0",
+                "true",
+                "null",
+                "ADMIN",
+                "$first",
+                r#""text""#,
+                r#""with \"quotes\", \\ and \t""#,
+                r#""multiple\nlines\n""#,
+                r#""trailing  \nspaces""#,
+                r#""""
+  block
+    string
+""""#,
+                r##""# not a comment""##,
+            ],
+        );
+        if depth == 0 {
+            return scalar;
+        }
+
+        match usize::arbitrary(g) % 3 {
+            0 => format!("[{}]", some(g, 0, 3, ", ", |g| value(g, depth - 1))),
+            1 => format!(
+                "{{{}}}",
+                some(g, 0, 3, ", ", |g| format!(
+                    "{}: {}",
+                    name(g),
+                    value(g, depth - 1)
+                ))
+            ),
+            _ => scalar,
+        }
+    }
+
+    fn arguments(g: &mut Gen) -> String {
+        maybe(g, |g| {
+            format!(
+                "({})",
+                some(g, 1, 3, ", ", |g| format!("{}: {}", name(g), value(g, 2)))
+            )
+        })
+    }
+
+    fn directives(g: &mut Gen) -> String {
+        some(g, 0, 2, "", |g| format!(" @{}{}", name(g), arguments(g)))
+    }
+
+    fn selection_set(g: &mut Gen, depth: usize) -> String {
+        let selections = some(g, 1, 4, "\n", |g| {
+            let selection = match usize::arbitrary(g) % 5 {
+                0 => format!("...{}{}", name(g), directives(g)),
+                1 if depth > 0 => format!(
+                    "...{}{} {}",
+                    maybe(g, |g| format!(" on {}", type_name(g))),
+                    directives(g),
+                    selection_set(g, depth - 1)
+                ),
+                _ => format!(
+                    "{}{}{}{}{}",
+                    maybe(g, |g| format!("{}: ", name(g))),
+                    name(g),
+                    arguments(g),
+                    directives(g),
+                    if depth > 0 {
+                        maybe(g, |g| format!(" {}", selection_set(g, depth - 1)))
+                    } else {
+                        String::new()
+                    }
+                ),
+            };
+            let trailing = maybe(g, |g| format!(" # {}", name(g)));
+            format!("{}{}{}", comment(g), selection, trailing)
+        });
+        format!("{{\n{}\n}}", selections)
+    }
+
+    fn variables(g: &mut Gen) -> String {
+        maybe(g, |g| {
+            let vars = some(g, 1, 3, ", ", |g| {
+                format!(
+                    "${}: {}{}",
+                    name(g),
+                    pick(g, &["Int", "[String!]", "User!"]),
+                    maybe(g, |g| format!(" = {}", value(g, 1).replace('$', "")))
+                )
+            });
+            format!("({})", vars)
+        })
+    }
+
+    fn query_definition(g: &mut Gen) -> String {
+        let definition = match usize::arbitrary(g) % 4 {
+            0 => selection_set(g, 3),
+            1 => format!(
+                "fragment {} on {}{} {}",
+                name(g),
+                type_name(g),
+                directives(g),
+                selection_set(g, 3)
+            ),
+            _ => format!(
+                "{} {}{}{} {}",
+                pick(g, &["query", "mutation", "subscription"]),
+                name(g),
+                variables(g),
+                directives(g),
+                selection_set(g, 3)
+            ),
+        };
+        format!("{}{}", comment(g), definition)
+    }
+
+    fn description(g: &mut Gen) -> String {
+        pick(
+            g,
+            &[
+                "",
+                "",
+                "\"A description\"\n",
+                "\"A description that is too long to fit on one line at most widths\"\n",
+                "\"\"\"\nA block\n\n  with an indented line\n\"\"\"\n",
+                "\"  indented\\nlines\"\n",
+                "\"\"\"\ntrailing  \nspaces\n\"\"\"\n",
+            ],
+        )
+    }
+
+    fn type_ref(g: &mut Gen) -> String {
+        pick(g, &["Int", "User!", "[User]", "[String!]!"])
+    }
+
+    fn input_value(g: &mut Gen) -> String {
+        format!(
+            "{}{}{}: {}{}{}",
+            comment(g),
+            description(g),
+            name(g),
+            type_ref(g),
+            maybe(g, |g| format!(" = {}", value(g, 2).replace('$', ""))),
+            directives(g).replace('$', "")
+        )
+    }
+
+    fn field(g: &mut Gen) -> String {
+        format!(
+            "{}{}{}{}: {}{}",
+            comment(g),
+            description(g),
+            name(g),
+            maybe(g, |g| format!("({})", some(g, 1, 3, "\n", input_value))),
+            type_ref(g),
+            directives(g).replace('$', "")
+        )
+    }
+
+    fn schema_definition(g: &mut Gen) -> String {
+        let directives = |g: &mut Gen| directives(g).replace('$', "");
+        let extend = maybe(g, |_| "extend ".to_string());
+        let desc = if extend.is_empty() {
+            description(g)
+        } else {
+            String::new()
+        };
+
+        let definition = match usize::arbitrary(g) % 7 {
+            0 => format!(
+                "{}{}type {}{}{} {{\n{}\n}}",
+                desc,
+                extend,
+                type_name(g),
+                maybe(g, |g| format!(
+                    " implements {}",
+                    some(g, 1, 3, " & ", type_name)
+                )),
+                directives(g),
+                some(g, 1, 4, "\n", field)
+            ),
+            1 => format!(
+                "{}{}interface {}{} {{\n{}\n}}",
+                desc,
+                extend,
+                type_name(g),
+                directives(g),
+                some(g, 1, 4, "\n", field)
+            ),
+            2 => format!(
+                "{}{}input {}{} {{\n{}\n}}",
+                desc,
+                extend,
+                type_name(g),
+                directives(g),
+                some(g, 1, 4, "\n", input_value)
+            ),
+            3 => format!(
+                "{}{}enum {}{} {{\n{}\n}}",
+                desc,
+                extend,
+                type_name(g),
+                directives(g),
+                some(g, 1, 4, "\n", |g| format!(
+                    "{}{}{}{}",
+                    comment(g),
+                    description(g),
+                    pick(g, &["ADMIN", "USER", "GUEST"]),
+                    directives(g)
+                ))
+            ),
+            4 => format!(
+                "{}{}union {}{} = {}",
+                desc,
+                extend,
+                type_name(g),
+                directives(g),
+                some(g, 1, 4, " | ", type_name)
+            ),
+            5 if extend.is_empty() => format!("{}scalar {}{}", desc, type_name(g), directives(g)),
+            5 => format!("extend scalar {} @{}", type_name(g), name(g)),
+            _ => format!(
+                "{}directive @{}{} on {}",
+                description(g),
+                name(g),
+                maybe(g, |g| format!("({})", some(g, 1, 3, ", ", input_value))),
+                some(g, 1, 3, " | ", |g| pick(g, &["FIELD", "QUERY", "OBJECT"]))
+            ),
+        };
+        format!("{}{}", comment(g), definition)
+    }
+
+    #[derive(Debug, Clone)]
+    struct Query(Input);
+
+    impl Arbitrary for Query {
+        fn arbitrary(g: &mut Gen) -> Query {
+            Query(Input {
+                source: some(g, 1, 3, "\n\n", query_definition),
+                config: arbitrary_config(g),
+            })
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Schema(Input);
+
+    impl Arbitrary for Schema {
+        fn arbitrary(g: &mut Gen) -> Schema {
+            Schema(Input {
+                source: some(g, 1, 4, "\n\n", schema_definition),
+                config: arbitrary_config(g),
+            })
+        }
+    }
+
+    /// Formatting succeeds, which includes the safety check, and formatting the output again
+    /// doesn't change it.
+    fn formats_idempotently<F>(formatter: F, input: &Input) -> TestResult
+    where
+        F: Fn(&str, &Config) -> Result<String, Error>,
+    {
+        let fail = |message: String| {
+            TestResult::error(format!(
+                "{:?}\n\n{}\n\n{}",
+                input.config, input.source, message
+            ))
+        };
+
+        let formatted = match formatter(&input.source, &input.config) {
+            Ok(formatted) => formatted,
+            Err(err) => return fail(err.to_string()),
+        };
+        match formatter(&formatted, &input.config) {
+            Ok(ref again) if *again == formatted => TestResult::passed(),
+            Ok(again) => fail(format!(
+                "formatted as\n\n{}\n\nand then as\n\n{}",
+                formatted, again
+            )),
+            Err(err) => fail(format!("formatted as\n\n{}\n\n{}", formatted, err)),
+        }
+    }
+
+    #[test]
+    fn queries_format_idempotently() {
+        fn prop(query: Query) -> TestResult {
+            formats_idempotently(query::format, &query.0)
+        }
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(prop as fn(Query) -> TestResult);
+    }
+
+    #[test]
+    fn queries_minify_idempotently() {
+        fn prop(query: Query) -> TestResult {
+            formats_idempotently(|source, _| minify(source), &query.0)
+        }
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(prop as fn(Query) -> TestResult);
+    }
+
+    #[test]
+    fn schemas_format_idempotently() {
+        fn prop(schema: Schema) -> TestResult {
+            formats_idempotently(schema::format, &schema.0)
+        }
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(prop as fn(Schema) -> TestResult);
+    }
+}
@@ -0,0 +1,77 @@
+//! Hashes that identify queries, for registering them with a gateway as persisted queries.
+
+use crate::format::minify::canonical;
+use failure::Error;
+use sha2::{Digest, Sha256};
+
+/// The SHA-256 of the canonical form of a query. Queries that only differ in whitespace,
+/// comments, or the order of their selections and arguments have the same hash.
+pub fn canonical_hash(contents: &str) -> Result<String, Error> {
+    Ok(sha256(&canonical(contents)?))
+}
+
+/// The hash of Apollo's automatic persisted queries, which is the SHA-256 of the exact text of
+/// the query that is sent.
+pub fn apq_hash(contents: &str) -> String {
+    sha256(contents)
+}
+
+/// The SHA-256 of `text` in lowercase hex.
+fn sha256(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hashes_in_hex() {
+        assert_eq!(
+            apq_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn same_canonical_hash() {
+        let query = "query Users { users(first: 10, after: $cursor) { name id } }";
+        let reformatted = "
+query Users {
+  # All of them
+  users(after: $cursor, first: 10) {
+    id
+    name
+  }
+}
+        ";
+        let changed = "query Users { users(first: 20, after: $cursor) { name id } }";
+
+        let hash = canonical_hash(query).unwrap();
+        assert_eq!(canonical_hash(reformatted).unwrap(), hash);
+        assert_ne!(canonical_hash(changed).unwrap(), hash);
+        assert_ne!(apq_hash(reformatted), apq_hash(query));
+    }
+
+    #[test]
+    fn same_hash_for_swapped_aliases() {
+        let query = "{ a: user(id: 1) { name } b: user(id: 2, first: 1) { name } }";
+        let swapped = "{ b: user(first: 1, id: 2) { name } a: user(id: 1) { name } }";
+
+        assert_eq!(
+            canonical_hash(query).unwrap(),
+            canonical_hash(swapped).unwrap()
+        );
+    }
+
+    #[test]
+    fn block_strings_hash_as_their_values() {
+        let block_string = "mutation { post(body: \"\"\"\n  First\n  Second\n\"\"\") }";
+        let quoted = "mutation { post(body: \"First\\nSecond\") }";
+
+        assert_eq!(
+            canonical_hash(block_string).unwrap(),
+            canonical_hash(quoted).unwrap()
+        );
+    }
+}
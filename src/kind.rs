@@ -0,0 +1,92 @@
+use failure::{bail, Error};
+use std::str::FromStr;
+
+/// Whether a document is a schema or holds queries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Schema,
+    Query,
+}
+
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "schema",
+    "type",
+    "interface",
+    "enum",
+    "input",
+    "scalar",
+    "union",
+    "directive",
+    "extend",
+];
+
+impl Kind {
+    /// Tell the kind of a document by its first definition. Schema definitions start with a
+    /// keyword like `type` or with a description, while queries start with `{`, `query`,
+    /// `mutation`, `subscription` or `fragment`.
+    pub fn detect(contents: &str) -> Kind {
+        let mut rest = contents;
+
+        loop {
+            rest =
+                rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',' || c == '\u{feff}');
+            if rest.starts_with('#') {
+                rest = rest.find('\n').map(|end| &rest[end..]).unwrap_or("");
+            } else {
+                break;
+            }
+        }
+
+        if rest.starts_with('"') {
+            return Kind::Schema;
+        }
+
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if SCHEMA_KEYWORDS.contains(&&rest[..end]) {
+            Kind::Schema
+        } else {
+            Kind::Query
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Kind, Error> {
+        Ok(match s {
+            "schema" => Kind::Schema,
+            "query" => Kind::Query,
+            _ => bail!("unknown kind \"{}\", expected schema or query", s),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_schema() {
+        assert_eq!(Kind::detect("type Query { a: Int }"), Kind::Schema);
+        assert_eq!(Kind::detect("extend type Query { a: Int }"), Kind::Schema);
+        assert_eq!(
+            Kind::detect("# Users\n\n\"\"\"\nA user\n\"\"\"\ntype User { id: ID }"),
+            Kind::Schema
+        );
+        assert_eq!(Kind::detect("\u{feff}scalar Date"), Kind::Schema);
+    }
+
+    #[test]
+    fn detect_query() {
+        assert_eq!(Kind::detect("{ user { id } }"), Kind::Query);
+        assert_eq!(Kind::detect("# type\nquery { a }"), Kind::Query);
+        assert_eq!(
+            Kind::detect("fragment typeFields on User { id }"),
+            Kind::Query
+        );
+        assert_eq!(Kind::detect(""), Kind::Query);
+    }
+}
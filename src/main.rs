@@ -1,20 +1,31 @@
-use failure::Error;
-use graphql_parser::parse_query;
-use lazy_static::lazy_static;
-use regex::Regex;
+use ast::{query, schema};
+use diagnostic::Diagnostic;
+use failure::{bail, format_err, Error};
+use format::parse::{parse_query, parse_schema};
+use kind::Kind;
+use report::{OutputFormat, Report, ReportFormat};
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, USER_AGENT},
     StatusCode,
 };
 use serde_json::{json, map::Map, Value};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 #[macro_use]
 mod macros;
 
+mod ast;
+mod diagnostic;
 mod diff;
+mod files;
 mod format;
+mod hash;
+mod kind;
+mod persist;
+mod report;
+mod validation;
 
 macro_rules! unwrap_or_exit {
     ( $e:expr, $msg:expr ) => {
@@ -32,36 +43,135 @@ macro_rules! unwrap_or_exit {
 #[derive(StructOpt, Debug)]
 #[structopt(name = "gqltools", about = "GraphQL tools")]
 enum Opt {
-    /// Validate a query by running it and seeing if it works
+    /// Validate queries against a schema
     #[structopt(name = "validate")]
     Validate {
         /// File path to the query to validate
         ///
-        /// Supports glob patterns such as "queries/**/*.graphql"
+        /// Directories and glob patterns such as "queries/**/*.graphql" are searched like for
+        /// format
         #[structopt(short = "q", long = "query")]
         query: String,
         /// File path to the schema
         #[structopt(short = "s", long = "schema")]
         schema: String,
+        /// Treat the files matching the query pattern as "schema" or "query" files
+        ///
+        /// By default it's inferred from their first definition. Schema files are skipped.
+        #[structopt(long = "kind")]
+        kind: Option<Kind>,
+        /// Print the problems as "human", "json", "junit", "sarif" or "github" annotations
+        #[structopt(long = "output-format", default_value = "human")]
+        output_format: OutputFormat,
     },
     /// Validate a schema for internal consistency
     #[structopt(name = "schema")]
     Schema {
         /// The file to validate
         file: String,
+        /// Print the problems as "human", "json", "junit", "sarif" or "github" annotations
+        #[structopt(long = "output-format", default_value = "human")]
+        output_format: OutputFormat,
     },
     /// Format a query or a schema
+    ///
+    /// The formatting is configured by a .gqltools.toml file in the current directory or
+    /// one of its parents.
     #[structopt(name = "format")]
     Format {
-        /// The file to format.
-        /// It'll be inferred from the contents if its a query or a schema.
-        file: String,
-        /// Write the formatted output back to the file
+        /// The files to format.
+        /// It'll be inferred from the contents if they are queries or schemas.
+        ///
+        /// Directories are searched for .graphql and .gql files, and glob patterns such as
+        /// "queries/**/*.graphql" are supported. Hidden files and files ignored by .gitignore
+        /// are skipped unless they are named explicitly. Without --write or --check, the
+        /// formatted output is printed, which needs a single file.
+        ///
+        /// "-" reads from stdin and writes to stdout. Parse errors are then printed to stderr
+        /// on a single line, as "path:line:column: error: message".
+        #[structopt(raw(required = "true"))]
+        files: Vec<String>,
+        /// The path of the file read from stdin, used for finding the .gqltools.toml file and
+        /// in error messages
+        #[structopt(long = "stdin-filepath", parse(from_os_str))]
+        stdin_filepath: Option<PathBuf>,
+        /// Format the files as "schema" or "query" instead of inferring it
+        #[structopt(long = "kind")]
+        kind: Option<Kind>,
+        /// Write the formatted output back to the files
         #[structopt(short = "w", long = "write")]
         write: bool,
-        /// Write the formatted output back to the file
+        /// Show how the files that aren't formatted would change, as a unified diff that patch
+        /// and git apply can apply, and exit with an error if there are any
         #[structopt(long = "check")]
         check: bool,
+        /// The number of unchanged lines shown around the changes by --check
+        #[structopt(short = "U", long = "context", default_value = "3")]
+        context: usize,
+        /// Print the files that aren't formatted with --check as "human" diffs, or as "json",
+        /// "junit", "sarif" or "github" annotations pointing at the lines that would change
+        #[structopt(long = "output-format", default_value = "human")]
+        output_format: OutputFormat,
+        /// Keep the source order of some kinds of nodes instead of sorting them
+        ///
+        /// A comma separated list of fields, enum-values, union-members, arguments,
+        /// selections or all. Applied on top of the sorting settings in .gqltools.toml
+        #[structopt(
+            long = "preserve-order",
+            raw(use_delimiter = "true", require_delimiter = "true")
+        )]
+        preserve_order: Vec<format::config::SortKind>,
+        /// Put queries on a single line, without comments and without the whitespace and
+        /// commas that aren't needed, for embedding them in requests
+        ///
+        /// Nothing is sorted, so the output parses to the same document as the input. Schemas
+        /// can't be minified
+        #[structopt(long = "minify")]
+        minify: bool,
+    },
+    /// Print the SHA-256 hashes of queries, for registering them as persisted queries
+    ///
+    /// The hash is of the canonical form of a query: minified, with its selections and
+    /// arguments sorted. Queries that only differ in formatting, comments or order have the
+    /// same hash. The output is a line like "hash  file" per file.
+    #[structopt(name = "hash")]
+    Hash {
+        /// The files with the queries to hash
+        ///
+        /// Directories and glob patterns are searched like for format
+        #[structopt(raw(required = "true"))]
+        files: Vec<String>,
+        /// Hash the exact text of the files instead, like Apollo's automatic persisted queries
+        #[structopt(long = "apq")]
+        apq: bool,
+        /// Print the canonical form of the query instead of its hash, for a single file
+        #[structopt(long = "canonical", conflicts_with = "apq")]
+        canonical: bool,
+    },
+    /// Write a manifest of persisted queries, for allowing only known operations on a server
+    ///
+    /// Every operation is listed with its hash, and with its document holding the operation
+    /// and the fragments it uses, which can be defined in any of the files. Operations must be
+    /// named, and operations and fragments can't share names.
+    ///
+    /// The documents are minified like with format --minify, so their text isn't the text of
+    /// the files: comments and formatting are left out, and block strings become quoted
+    /// strings with the same value.
+    #[structopt(name = "persist")]
+    Persist {
+        /// The files with the operations and fragments
+        ///
+        /// Directories and glob patterns are searched like for format. Schema files are
+        /// skipped
+        #[structopt(raw(required = "true"))]
+        files: Vec<String>,
+        /// The file to write the manifest to, instead of stdout
+        #[structopt(short = "o", long = "output")]
+        output: Option<String>,
+        /// Hash the documents like Apollo's automatic persisted queries, instead of hashing
+        /// their canonical forms
+        #[structopt(long = "apq")]
+        apq: bool,
     },
     /// Run a query against a GraphQL web service
     #[structopt(name = "run")]
@@ -90,9 +200,59 @@ fn main() {
     let opt = Opt::from_args();
 
     let res = match opt {
-        Opt::Validate { query, schema } => validate_query(query, schema),
-        Opt::Schema { file } => validate_schema(file),
-        Opt::Format { file, write, check } => format(file, write, check),
+        Opt::Validate {
+            query,
+            schema,
+            kind,
+            output_format,
+        } => validate_query(query, schema, kind, output_format),
+        Opt::Schema {
+            file,
+            output_format,
+        } => validate_schema(file, output_format),
+        Opt::Format {
+            files,
+            stdin_filepath,
+            kind,
+            write,
+            check,
+            context,
+            output_format,
+            preserve_order,
+            minify,
+        } => {
+            if !check && output_format != OutputFormat::Human {
+                eprintln!("format can only use --output-format with --check");
+                std::process::exit(1);
+            }
+            let check = if check {
+                Some(Check {
+                    context,
+                    output_format,
+                })
+            } else {
+                None
+            };
+            if files.iter().any(|file| file == "-") {
+                format_stdin(
+                    files,
+                    stdin_filepath,
+                    kind,
+                    write,
+                    check,
+                    preserve_order,
+                    minify,
+                )
+            } else {
+                format(files, kind, write, check, preserve_order, minify)
+            }
+        }
+        Opt::Hash {
+            files,
+            apq,
+            canonical,
+        } => hash(files, apq, canonical),
+        Opt::Persist { files, output, apq } => persist(files, output, apq),
         Opt::Run {
             file,
             host,
@@ -112,25 +272,79 @@ fn main() {
 
 type Output = Result<(), Error>;
 
-fn validate_query(query_path: String, schema_path: String) -> Output {
-    use glob::glob;
+fn validate_query(
+    query_path: String,
+    schema_path: String,
+    kind: Option<Kind>,
+    output_format: OutputFormat,
+) -> Output {
     use colored::*;
 
+    let schema_contents = read_file(&schema_path)?;
+    let schema_doc = parse_schema_file(&schema_path, &schema_contents)?;
+    let schema = validation::schema::Schema::new(&schema_doc);
+
+    let mut results = Vec::new();
+    for file in files::expand(&[query_path])? {
+        let path = file.to_string_lossy().into_owned();
+        let contents = match read_file(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                results.push((path, Err(err)));
+                continue;
+            }
+        };
+        if kind.unwrap_or_else(|| Kind::detect(&contents)) == Kind::Query {
+            let result = perform_validation(&path, &contents, &schema);
+            results.push((path, result));
+        }
+    }
+
+    if let OutputFormat::Report(format) = output_format {
+        let mut report = Report::new("validate");
+        let mut failed = false;
+        for (file, result) in results {
+            match with_parse_error(result) {
+                Ok(diagnostics) => report.add(&file, diagnostics),
+                Err(err) => {
+                    eprintln!("{}: {}", file, err);
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            print!("{}", report.render(format));
+            std::process::exit(1);
+        }
+        return print_report(&report, format);
+    }
+
     let mut all_good = true;
     let mut lines = Vec::new();
 
-    glob(&query_path)?
-        .filter_map(|file| file.ok())
-        .map(|file| file.to_string_lossy().into_owned())
-        .filter(|file| !is_schema(&read_file(file).expect("unreadable file from glob")))
-        .for_each(|file| {
-            match perform_validation(&file, &schema_path) {
-                Ok(()) => {
+    results
+        .into_iter()
+        .for_each(|(file, result)| {
+            match result {
+                Ok(ref errors) if errors.is_empty() => {
                     lines.push(format!("{} {}", "OK:".green(), file));
                 },
+                Ok(errors) => {
+                    lines.push(format!("{} {}", "Error:".red(), file));
+                    for error in errors {
+                        lines.push(format!(
+                            "{} {}:{}: {}",
+                            "Error message:".red(),
+                            file,
+                            error.pos,
+                            error.message
+                        ));
+                    }
+                    all_good = false;
+                }
                 Err(err) => {
                     lines.push(format!("{} {}", "Error:".red(), file));
-                    lines.push(format!("{} {}", "Error message:".red(), err.to_string()));
+                    lines.push(err.to_string());
                     all_good = false;
                 }
             }
@@ -147,62 +361,377 @@ fn validate_query(query_path: String, schema_path: String) -> Output {
     Ok(())
 }
 
-fn perform_validation(query_path: &str, schema_path: &str) -> Result<(), Error> {
-    use graphql_client_codegen::{generate_module_token_stream, GraphQLClientDeriveOptions};
-    use std::path::{Path, PathBuf};
-    use syn::Visibility;
-
-    let query_path = PathBuf::from(query_path);
-    let schema_path = Path::new(schema_path);
-    let options = GraphQLClientDeriveOptions {
-        operation_name: Some("OperationName".into()),
-        struct_name: Some("StructName".into()),
-        module_name: Some("module_name".into()),
-        additional_derives: None,
-        deprecation_strategy: None,
-        module_visibility: Visibility::Inherited,
-    };
-    generate_module_token_stream(query_path, schema_path, Some(options)).map(|_| ())
+fn perform_validation(
+    query_path: &str,
+    contents: &str,
+    schema: &validation::schema::Schema,
+) -> Result<Vec<Diagnostic>, Error> {
+    let doc = parse_query_file(query_path, contents)?;
+    let errors = validation::query::validate(&doc, schema);
+    Ok(validation_diagnostics(query_path, contents, errors))
 }
 
-fn validate_schema(_: String) -> Output {
-    unimplemented!()
+fn validate_schema(file: String, output_format: OutputFormat) -> Output {
+    use colored::*;
+
+    let contents = read_file(&file)?;
+    let result = parse_schema_file(&file, &contents).map(|doc| {
+        let errors = validation::schema::validate(&doc);
+        validation_diagnostics(&file, &contents, errors)
+    });
+
+    if let OutputFormat::Report(format) = output_format {
+        let mut report = Report::new("schema");
+        report.add(&file, with_parse_error(result)?);
+        return print_report(&report, format);
+    }
+
+    let errors = result?;
+    if errors.is_empty() {
+        println!("{} {}", "OK:".green(), file);
+        return Ok(());
+    }
+
+    for error in errors {
+        println!(
+            "{} {}:{}: {}",
+            "Error:".red(),
+            file,
+            error.pos,
+            error.message
+        );
+    }
+    std::process::exit(1);
+}
+
+fn validation_diagnostics(
+    path: &str,
+    source: &str,
+    errors: Vec<validation::ValidationError>,
+) -> Vec<Diagnostic> {
+    errors
+        .into_iter()
+        .map(|error| Diagnostic::new(path, source, error.pos, error.message))
+        .collect()
+}
+
+/// The diagnostics of a file, with the error of parsing it as one. Other errors, like the file
+/// not being readable, are returned.
+fn with_parse_error(result: Result<Vec<Diagnostic>, Error>) -> Result<Vec<Diagnostic>, Error> {
+    result.or_else(|err| {
+        err.downcast::<Diagnostic>()
+            .map(|diagnostic| vec![diagnostic])
+    })
 }
 
-fn format(file_path: String, write: bool, check: bool) -> Output {
-    if write && check {
+/// Print a report for CI systems, and exit with an error if it has any problems.
+fn print_report(report: &Report, format: ReportFormat) -> Output {
+    print!("{}", report.render(format));
+    if report.has_problems() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The settings of `format --check`.
+struct Check {
+    /// The number of unchanged lines shown around the changes in diffs.
+    context: usize,
+    output_format: OutputFormat,
+}
+
+fn format(
+    paths: Vec<String>,
+    kind: Option<Kind>,
+    write: bool,
+    check: Option<Check>,
+    preserve_order: Vec<format::config::SortKind>,
+    minify: bool,
+) -> Output {
+    use rayon::prelude::*;
+
+    if write && check.is_some() {
         eprintln!("format cannot both check and write");
         std::process::exit(1);
     }
 
-    let contents = read_file(&file_path)?;
-    let contents = contents.trim();
+    let config = format_config(&std::env::current_dir()?, preserve_order)?;
 
-    let formatted = if is_schema(&contents) {
-        format::schema::format(&contents)?
-    } else {
-        format::query::format(&contents)?
-    };
+    let files = files::expand(&paths)?;
+    if files.len() > 1 && !write && check.is_none() {
+        eprintln!("format can only print a single file, use --write or --check for several");
+        std::process::exit(1);
+    }
+
+    let results = files
+        .par_iter()
+        .map(|file| format_file(file, kind, &config, minify))
+        .collect::<Vec<_>>();
+
+    let mut failed = 0;
+    let mut unformatted = 0;
+    let mut report = Report::new("format");
+    let output_format = check.as_ref().map(|check| check.output_format);
+
+    for (file, result) in files.iter().zip(results) {
+        let path = file.to_string_lossy();
+        let (source, formatted) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                match (output_format, err.downcast_ref::<Diagnostic>()) {
+                    (Some(OutputFormat::Report(_)), Some(diagnostic)) => {
+                        report.add(&path, vec![diagnostic.clone()])
+                    }
+                    _ => eprintln!("{}", err),
+                }
+                failed += 1;
+                continue;
+            }
+        };
+
+        if write {
+            if formatted != source.trim() {
+                write_file(path.into_owned(), formatted_file(&formatted))?;
+            }
+        } else if let Some(check) = &check {
+            if formatted == source.trim() {
+                report.add(&path, Vec::new());
+                continue;
+            }
+            if check.output_format == OutputFormat::Human {
+                print_diff(&path, &source, &formatted, check.context);
+            } else {
+                report.add(&path, unformatted_diagnostics(&path, &source, &formatted));
+            }
+            unformatted += 1;
+        } else {
+            println!("{}", formatted);
+        }
+    }
 
+    // The summary goes to stderr, so that the diffs can be piped to patch.
+    if let Some(OutputFormat::Human) = output_format {
+        if unformatted == 0 {
+            eprintln!(
+                "{} of {} files are formatted",
+                files.len() - failed,
+                files.len()
+            );
+        } else {
+            eprintln!(
+                "{} of {} files would be reformatted",
+                unformatted,
+                files.len()
+            );
+        }
+    } else if let Some(OutputFormat::Report(format)) = output_format {
+        print!("{}", report.render(format));
+    }
+
+    if failed > 0 || unformatted > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn format_stdin(
+    paths: Vec<String>,
+    stdin_filepath: Option<PathBuf>,
+    kind: Option<Kind>,
+    write: bool,
+    check: Option<Check>,
+    preserve_order: Vec<format::config::SortKind>,
+    minify: bool,
+) -> Output {
+    use std::io::prelude::*;
+
+    if paths.len() > 1 {
+        bail!("format cannot read from stdin and from files at the same time");
+    }
     if write {
-        write_file(file_path, formatted)?;
-    } else if check {
-        if formatted != contents {
-            print_diff(&formatted, &contents);
+        bail!("format cannot write the output back to stdin");
+    }
+
+    let mut dir = std::env::current_dir()?;
+    if let Some(parent) = stdin_filepath.as_ref().and_then(|path| path.parent()) {
+        dir = dir.join(parent);
+    }
+    let config = format_config(&dir, preserve_order)?;
+
+    let path = match &stdin_filepath {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => "<stdin>".to_string(),
+    };
+    let mut source = String::new();
+    std::io::stdin().read_to_string(&mut source)?;
+
+    let result = format_source(&path, &source, kind, &config, minify);
+
+    if let Some(check) = check.as_ref() {
+        if let OutputFormat::Report(format) = check.output_format {
+            let diagnostics =
+                result.map(|formatted| unformatted_diagnostics(&path, &source, &formatted));
+            let mut report = Report::new("format");
+            report.add(&path, with_parse_error(diagnostics)?);
+            return print_report(&report, format);
+        }
+    }
+
+    let formatted = match result {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            match err.downcast_ref::<Diagnostic>() {
+                Some(diagnostic) => eprintln!("{}", diagnostic.short()),
+                None => eprintln!("{}", err),
+            }
             std::process::exit(1);
         }
+    };
+
+    match check {
+        None => println!("{}", formatted),
+        Some(check) if formatted != source.trim() => {
+            print_diff(&path, &source, &formatted, check.context);
+            std::process::exit(1);
+        }
+        Some(_) => {}
+    }
+
+    Ok(())
+}
+
+/// The settings in the config file for files in `dir`, adjusted by the command line options.
+fn format_config(
+    dir: &Path,
+    preserve_order: Vec<format::config::SortKind>,
+) -> Result<format::config::Config, Error> {
+    let mut config = format::config::Config::find(dir)?;
+    for kind in preserve_order {
+        config.sort.preserve(kind);
+    }
+    Ok(config)
+}
+
+/// Format a file, returning its contents and the formatted version of them.
+fn format_file(
+    file: &Path,
+    kind: Option<Kind>,
+    config: &format::config::Config,
+    minify: bool,
+) -> Result<(String, String), Error> {
+    let path = file.to_string_lossy();
+    let source = read_file(&path).map_err(|err| format_err!("{}: {}", path, err))?;
+    let formatted = format_source(&path, &source, kind, config, minify)?;
+
+    Ok((source, formatted))
+}
+
+fn format_source(
+    path: &str,
+    source: &str,
+    kind: Option<Kind>,
+    config: &format::config::Config,
+    minify: bool,
+) -> Result<String, Error> {
+    // Parse the source as it is, so that the positions of parse errors match its lines.
+    let formatted = match kind.unwrap_or_else(|| Kind::detect(source)) {
+        Kind::Schema if minify => Err(format_err!("schemas can't be minified")),
+        Kind::Schema => format::schema::format(source, config),
+        Kind::Query if minify => format::minify::minify(source),
+        Kind::Query => format::query::format(source, config),
+    };
+    formatted.map_err(|err| file_error(path, source, err))
+}
+
+/// Turn the error of parsing `source` into a diagnostic, and put the path in front of other
+/// errors.
+fn file_error(path: &str, source: &str, err: Error) -> Error {
+    let err = Diagnostic::from_parse_error(path, source, err);
+    if err.downcast_ref::<Diagnostic>().is_some() {
+        err
     } else {
-        println!("{}", formatted);
+        format_err!("{}: {}", path, err)
+    }
+}
+
+fn hash(paths: Vec<String>, apq: bool, canonical: bool) -> Output {
+    let files = files::expand(&paths)?;
+    if canonical && files.len() > 1 {
+        bail!("hash can only print the canonical form of a single file");
+    }
+
+    let mut failed = false;
+    for file in files {
+        let path = file.to_string_lossy();
+        let hashed = read_file(&path)
+            .map_err(|err| format_err!("{}: {}", path, err))
+            .and_then(|contents| {
+                let result = if canonical {
+                    format::minify::canonical(&contents)
+                } else if apq {
+                    parse_query_file(&path, &contents).map(|_| hash::apq_hash(&contents))
+                } else {
+                    hash::canonical_hash(&contents)
+                };
+                result.map_err(|err| file_error(&path, &contents, err))
+            });
+
+        match hashed {
+            Ok(output) if canonical => println!("{}", output),
+            Ok(hash) => println!("{}  {}", hash, path),
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
-fn is_schema(contents: &str) -> bool {
-    lazy_static! {
-        static ref schema_re: Regex = Regex::new(r"^schema").unwrap();
+fn persist(paths: Vec<String>, output: Option<String>, apq: bool) -> Output {
+    let mut documents = Vec::new();
+    let mut failed = false;
+
+    for file in files::expand(&paths)? {
+        let path = file.to_string_lossy().into_owned();
+        let contents = read_file(&path).map_err(|err| format_err!("{}: {}", path, err))?;
+        if Kind::detect(&contents) == Kind::Schema {
+            continue;
+        }
+
+        match parse_query_file(&path, &contents) {
+            Ok(doc) => documents.push((path, doc)),
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
     }
-    contents.lines().any(|line| schema_re.is_match(line))
+
+    let hash = if apq {
+        persist::Hash::Apq
+    } else {
+        persist::Hash::Canonical
+    };
+    let manifest = persist::Manifest::new(&documents, hash)?;
+    let json = serde_json::to_string_pretty(&manifest)?;
+
+    match output {
+        Some(output) => write_file(output, format!("{}\n", json))?,
+        None => println!("{}", json),
+    }
+
+    Ok(())
 }
 
 fn read_file(file: &str) -> Result<String, Error> {
@@ -215,6 +744,14 @@ fn read_file(file: &str) -> Result<String, Error> {
     Ok(contents)
 }
 
+fn parse_query_file(path: &str, contents: &str) -> Result<query::Document, Error> {
+    parse_query(contents).map_err(|err| Diagnostic::from_parse_error(path, contents, err.into()))
+}
+
+fn parse_schema_file(path: &str, contents: &str) -> Result<schema::Document, Error> {
+    parse_schema(contents).map_err(|err| Diagnostic::from_parse_error(path, contents, err.into()))
+}
+
 fn write_file(file_path: String, out: String) -> Result<(), Error> {
     use std::fs::File;
     use std::io::prelude::*;
@@ -224,10 +761,36 @@ fn write_file(file_path: String, out: String) -> Result<(), Error> {
     Ok(())
 }
 
-fn print_diff(formatted: &str, contents: &str) {
+/// The contents of a formatted file, which ends with a newline.
+fn formatted_file(formatted: &str) -> String {
+    format!("{}\n", formatted)
+}
+
+/// A diagnostic for each place `--write` would change in the file at `path`, if it isn't
+/// formatted.
+fn unformatted_diagnostics(path: &str, source: &str, formatted: &str) -> Vec<Diagnostic> {
+    if formatted == source.trim() {
+        return Vec::new();
+    }
+
+    let last_line = source.lines().count().max(1);
+    diff::make_diff(source, &formatted_file(formatted), 0)
+        .into_iter()
+        .map(|mismatch| {
+            // A hunk that only adds lines starts at the line they go in front of.
+            let line = (mismatch.line_number_orig as usize).max(1).min(last_line);
+            let pos = graphql_parser::Pos { line, column: 1 };
+            Diagnostic::new(path, source, pos, "not formatted")
+        })
+        .collect()
+}
+
+/// Print how `--write` would change the file at `path`, as a unified diff with `context`
+/// unchanged lines around the changes.
+fn print_diff(path: &str, source: &str, formatted: &str, context: usize) {
     use self::diff;
-    let diff = diff::make_diff(contents, formatted, formatted.len());
-    diff::print_diff(diff);
+    let diff = diff::make_diff(source, &formatted_file(formatted), context);
+    diff::print_diff(path, diff);
 }
 
 fn run(file: String, host: String, headers: Vec<String>, vars: Vec<String>) -> Result<(), Error> {
@@ -251,7 +814,7 @@ fn run_2(
     vars: Vec<String>,
 ) -> Result<(Value, StatusCode), Error> {
     let contents = read_file(&file)?;
-    parse_query(&contents)?;
+    parse_query_file(&file, &contents)?;
 
     let mut map = Map::new();
     map.insert("query".to_string(), json!(contents));
@@ -0,0 +1,233 @@
+//! Manifests of persisted queries, which list the operations of an app with their hashes, so
+//! that a server can allow only those operations.
+
+use crate::ast::query::*;
+use crate::format::minify::minify_document;
+use crate::hash;
+use failure::{bail, Error};
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    /// Sorted by name.
+    pub operations: Vec<PersistedOperation>,
+}
+
+/// An operation, with the fragments it uses in the same document.
+#[derive(Debug, Serialize)]
+pub struct PersistedOperation {
+    pub name: String,
+    pub hash: String,
+    /// The minified document, which is the text that is hashed and that clients send, rather
+    /// than the text of the files.
+    pub document: String,
+}
+
+/// How the documents are hashed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hash {
+    /// The hash of the canonical form of the document, like `gqltools hash`.
+    Canonical,
+    /// The hash of the exact document, like Apollo's automatic persisted queries.
+    Apq,
+}
+
+impl Manifest {
+    /// Collect the operations of the query files in `documents`, given with their paths.
+    /// Operations can use the fragments of all files. Anonymous operations, operations or
+    /// fragments with the same name, and fragments that aren't defined are errors.
+    pub fn new(documents: &[(String, Document)], hash: Hash) -> Result<Manifest, Error> {
+        let mut errors = Vec::new();
+        let mut operations = BTreeMap::new();
+        let mut fragments = HashMap::new();
+
+        for (path, doc) in documents {
+            for def in &doc.definitions {
+                match def {
+                    Definition::Operation(op) => match operation_name(op) {
+                        Some(name) => {
+                            if let Some((other, _)) = operations.insert(name, (path, op)) {
+                                errors.push(format!(
+                                    "operation {} is defined in both {} and {}",
+                                    name, other, path
+                                ));
+                            }
+                        }
+                        None => errors.push(format!(
+                            "{}: operations must be named to be persisted",
+                            path
+                        )),
+                    },
+                    Definition::Fragment(frag) => {
+                        if let Some((other, _)) = fragments.insert(&frag.name, (path, frag)) {
+                            errors.push(format!(
+                                "fragment {} is defined in both {} and {}",
+                                frag.name, other, path
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut persisted = Vec::new();
+        for (name, (path, op)) in operations {
+            let mut used = BTreeSet::new();
+            let mut missing = BTreeSet::new();
+            used_fragments(selection_set(op), &fragments, &mut used, &mut missing);
+
+            for fragment in missing {
+                errors.push(format!(
+                    "{}: operation {} uses fragment {}, which isn't defined",
+                    path, name, fragment
+                ));
+            }
+            if !errors.is_empty() {
+                continue;
+            }
+
+            let mut definitions = vec![Definition::Operation(op.clone())];
+            for fragment in used {
+                let (_, frag) = fragments[fragment];
+                definitions.push(Definition::Fragment(frag.clone()));
+            }
+
+            let document = minify_document(Document { definitions })?;
+            let hash = match hash {
+                Hash::Canonical => hash::canonical_hash(&document)?,
+                Hash::Apq => hash::apq_hash(&document),
+            };
+            persisted.push(PersistedOperation {
+                name: name.to_string(),
+                hash,
+                document,
+            });
+        }
+
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"));
+        }
+        Ok(Manifest {
+            operations: persisted,
+        })
+    }
+}
+
+fn operation_name(op: &OperationDefinition) -> Option<&str> {
+    let name = match op {
+        OperationDefinition::SelectionSet(_) => &None,
+        OperationDefinition::Query(query) => &query.name,
+        OperationDefinition::Mutation(mutation) => &mutation.name,
+        OperationDefinition::Subscription(sub) => &sub.name,
+    };
+    name.as_ref().map(String::as_str)
+}
+
+fn selection_set(op: &OperationDefinition) -> &SelectionSet {
+    match op {
+        OperationDefinition::SelectionSet(set) => set,
+        OperationDefinition::Query(query) => &query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mutation.selection_set,
+        OperationDefinition::Subscription(sub) => &sub.selection_set,
+    }
+}
+
+/// Collect the names of the fragments spread in `set`, and in the fragments they use.
+fn used_fragments<'a>(
+    set: &'a SelectionSet,
+    fragments: &HashMap<&'a String, (&String, &'a FragmentDefinition)>,
+    used: &mut BTreeSet<&'a String>,
+    missing: &mut BTreeSet<&'a String>,
+) {
+    for selection in &set.items {
+        match selection {
+            Selection::Field(field) => {
+                used_fragments(&field.selection_set, fragments, used, missing)
+            }
+            Selection::InlineFragment(inline_frag) => {
+                used_fragments(&inline_frag.selection_set, fragments, used, missing)
+            }
+            Selection::FragmentSpread(frag_spread) => {
+                let name = &frag_spread.fragment_name;
+                match fragments.get(name) {
+                    Some((_, frag)) => {
+                        if used.insert(name) {
+                            used_fragments(&frag.selection_set, fragments, used, missing);
+                        }
+                    }
+                    None => {
+                        missing.insert(name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::parse::parse_query;
+
+    fn manifest(files: &[(&str, &str)]) -> Result<Manifest, Error> {
+        let documents = files
+            .iter()
+            .map(|(path, contents)| (path.to_string(), parse_query(contents).unwrap()))
+            .collect::<Vec<_>>();
+        Manifest::new(&documents, Hash::Canonical)
+    }
+
+    #[test]
+    fn fragments_from_other_files() {
+        let manifest = manifest(&[
+            (
+                "users.graphql",
+                "query Users { users { ...userFields } } query Viewer { viewer { id } }",
+            ),
+            (
+                "fragments.graphql",
+                "fragment userFields on User { id ...names } fragment names on User { name }
+                 fragment unused on User { id }",
+            ),
+        ])
+        .unwrap();
+
+        let documents = manifest
+            .operations
+            .iter()
+            .map(|op| (op.name.as_str(), op.document.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            documents,
+            vec![
+                (
+                    "Users",
+                    "query Users{users{...userFields}}fragment names on User{name}\
+                     fragment userFields on User{id...names}"
+                ),
+                ("Viewer", "query Viewer{viewer{id}}"),
+            ]
+        );
+        assert_eq!(
+            manifest.operations[1].hash,
+            hash::canonical_hash("query Viewer { viewer { id } }").unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = manifest(&[
+            ("a.graphql", "query A { a } { b }"),
+            ("b.graphql", "query A { a ...missing }"),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "a.graphql: operations must be named to be persisted
+operation A is defined in both a.graphql and b.graphql
+b.graphql: operation A uses fragment missing, which isn't defined"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Reports of the problems `validate`, `schema` and `format --check` find, in the formats that
+//! CI systems read. All of them are made from the same diagnostics.
+
+use crate::diagnostic::Diagnostic;
+use failure::{bail, Error};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// How a command prints the problems it finds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The colored output for people, which differs per command.
+    Human,
+    /// A report for CI systems.
+    Report(ReportFormat),
+}
+
+/// The formats of reports for CI systems, which are the same for all commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    /// A JSON array with an object per diagnostic.
+    Json,
+    /// JUnit XML with a test case per file.
+    Junit,
+    /// A SARIF 2.1.0 log, for code scanning tools.
+    Sarif,
+    /// `::error` workflow commands, which GitHub Actions shows as annotations.
+    Github,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<OutputFormat, Error> {
+        Ok(match s {
+            "human" => OutputFormat::Human,
+            "json" => OutputFormat::Report(ReportFormat::Json),
+            "junit" => OutputFormat::Report(ReportFormat::Junit),
+            "sarif" => OutputFormat::Report(ReportFormat::Sarif),
+            "github" => OutputFormat::Report(ReportFormat::Github),
+            _ => bail!(
+                "unknown output format \"{}\", expected human, json, junit, sarif or github",
+                s
+            ),
+        })
+    }
+}
+
+/// The files a command checked and the problems it found in them.
+pub struct Report {
+    command: String,
+    files: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn new(command: &str) -> Report {
+        Report {
+            command: command.to_string(),
+            files: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Add a checked file with the problems found in it, if any.
+    pub fn add(&mut self, path: &str, diagnostics: Vec<Diagnostic>) {
+        self.files.push(path.to_string());
+        self.diagnostics.extend(diagnostics);
+    }
+
+    pub fn has_problems(&self) -> bool {
+        !self.diagnostics.is_empty()
+    }
+
+    /// The report in one of the formats for CI systems. The commands print the human output
+    /// themselves, as it differs per command.
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.json(),
+            ReportFormat::Junit => self.junit(),
+            ReportFormat::Sarif => self.sarif(),
+            ReportFormat::Github => self.github(),
+        }
+    }
+
+    fn json(&self) -> String {
+        let diagnostics = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "path": diagnostic.path,
+                    "line": diagnostic.pos.line,
+                    "column": diagnostic.pos.column,
+                    "message": diagnostic.message,
+                })
+            })
+            .collect::<Vec<_>>();
+        format!("{}\n", pretty(&Value::Array(diagnostics)))
+    }
+
+    fn junit(&self) -> String {
+        let name = escape_xml(&format!("gqltools {}", self.command));
+        let failures = self
+            .files
+            .iter()
+            .filter(|file| self.diagnostics_of(file).next().is_some())
+            .count();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            name,
+            self.files.len(),
+            failures
+        ));
+        for file in &self.files {
+            let diagnostics = self.diagnostics_of(file).collect::<Vec<_>>();
+            let testcase = format!(
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(file),
+                name
+            );
+            if diagnostics.is_empty() {
+                xml.push_str(&format!("{}/>\n", testcase));
+                continue;
+            }
+
+            let details = diagnostics
+                .iter()
+                .map(|diagnostic| escape_xml(&diagnostic.short()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            xml.push_str(&format!("{}>\n", testcase));
+            xml.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape_xml(&diagnostics[0].message),
+                details
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    fn sarif(&self) -> String {
+        let results = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "level": "error",
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file_uri(&diagnostic.path) },
+                            "region": {
+                                "startLine": diagnostic.pos.line.max(1),
+                                "startColumn": diagnostic.pos.column.max(1),
+                            },
+                        },
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "gqltools",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                },
+                "results": results,
+            }],
+        });
+        format!("{}\n", pretty(&log))
+    }
+
+    fn github(&self) -> String {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| {
+                format!(
+                    "::error file={},line={},col={}::{}\n",
+                    escape_github_property(&diagnostic.path),
+                    diagnostic.pos.line,
+                    diagnostic.pos.column,
+                    escape_github_data(&diagnostic.message)
+                )
+            })
+            .collect()
+    }
+
+    fn diagnostics_of<'a>(&'a self, file: &'a str) -> impl Iterator<Item = &'a Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.path == file)
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).expect("JSON values always serialize")
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A relative URI for the file at `path`, with `/` separators and the other characters that
+/// can't be in a URI percent-encoded.
+fn file_uri(path: &str) -> String {
+    let mut uri = String::with_capacity(path.len());
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// The message of a workflow command, which ends at the end of the line.
+fn escape_github_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// A property of a workflow command, which also ends at `,` and `::`.
+fn escape_github_property(s: &str) -> String {
+    escape_github_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use graphql_parser::Pos;
+
+    fn report() -> Report {
+        let mut report = Report::new("validate");
+        report.add("ok.graphql", Vec::new());
+        report.add(
+            "a, b.graphql",
+            vec![
+                Diagnostic::new(
+                    "a, b.graphql",
+                    "{ user }",
+                    Pos { line: 1, column: 3 },
+                    "Cannot query field \"user\" on type \"Query\".",
+                ),
+                Diagnostic::new(
+                    "a, b.graphql",
+                    "{ user }",
+                    Pos { line: 1, column: 8 },
+                    "a <b>",
+                ),
+            ],
+        );
+        report
+    }
+
+    #[test]
+    fn json() {
+        let json: Value = serde_json::from_str(&report().render(ReportFormat::Json)).unwrap();
+
+        assert_eq!(
+            json[0],
+            json!({
+                "path": "a, b.graphql",
+                "line": 1,
+                "column": 3,
+                "message": "Cannot query field \"user\" on type \"Query\".",
+            })
+        );
+        assert_eq!(json.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn junit() {
+        assert_eq!(
+            report().render(ReportFormat::Junit),
+            r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="gqltools validate" tests="2" failures="1">
+    <testcase name="ok.graphql" classname="gqltools validate"/>
+    <testcase name="a, b.graphql" classname="gqltools validate">
+      <failure message="Cannot query field &quot;user&quot; on type &quot;Query&quot;.">a, b.graphql:1:3: error: Cannot query field &quot;user&quot; on type &quot;Query&quot;.
+a, b.graphql:1:8: error: a &lt;b&gt;</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+            "#
+            .trim_start()
+            .trim_end_matches(' ')
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        let sarif: Value = serde_json::from_str(&report().render(ReportFormat::Sarif)).unwrap();
+        let result = &sarif["runs"][0]["results"][1];
+
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(result["message"]["text"], "a <b>");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "a%2C%20b.graphql" },
+                "region": { "startLine": 1, "startColumn": 8 },
+            })
+        );
+    }
+
+    #[test]
+    fn github() {
+        let mut report = report();
+        report.add(
+            "c.graphql",
+            vec![Diagnostic::new(
+                "c.graphql",
+                "",
+                Pos { line: 2, column: 1 },
+                "100% wrong\non two lines",
+            )],
+        );
+
+        assert_eq!(
+            report.render(ReportFormat::Github),
+            "::error file=a%2C b.graphql,line=1,col=3::Cannot query field \"user\" on type \
+             \"Query\".\n\
+             ::error file=a%2C b.graphql,line=1,col=8::a <b>\n\
+             ::error file=c.graphql,line=2,col=1::100%25 wrong%0Aon two lines\n"
+        );
+    }
+}
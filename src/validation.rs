@@ -0,0 +1,31 @@
+use graphql_parser::Pos;
+use std::fmt;
+
+pub mod query;
+pub mod schema;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub pos: Pos,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new<T: Into<String>>(pos: Pos, message: T) -> ValidationError {
+        ValidationError {
+            pos,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.pos, self.message)
+    }
+}
+
+fn sort_errors(errors: &mut Vec<ValidationError>) {
+    errors.sort_by(|a, b| a.pos.cmp(&b.pos).then_with(|| a.message.cmp(&b.message)));
+    errors.dedup();
+}
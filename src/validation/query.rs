@@ -0,0 +1,1210 @@
+use super::schema::{is_non_null, named_type, Schema, TypeInfo, TypeKind};
+use super::{sort_errors, ValidationError};
+use crate::ast::query::*;
+use crate::ast::schema::{DirectiveLocation, InputValue};
+use graphql_parser::Pos;
+use std::collections::{HashMap, HashSet};
+
+/// Validate an executable document against a schema, following the validation section of the
+/// spec.
+///
+/// All errors are returned, ordered by their position in the document.
+pub fn validate<'a>(doc: &'a Document, schema: &'a Schema<'a>) -> Vec<ValidationError> {
+    let mut validator = Validator {
+        schema,
+        fragments: HashMap::new(),
+        fragment_scopes: HashMap::new(),
+        scope: Scope::default(),
+        errors: Vec::new(),
+    };
+    validator.validate_document(doc);
+
+    let mut errors = validator.errors;
+    sort_errors(&mut errors);
+    errors
+}
+
+struct Operation<'a> {
+    kind: &'static str,
+    name: Option<&'a str>,
+    position: Pos,
+    variable_definitions: &'a [VariableDefinition],
+    directives: &'a [Directive],
+    selection_set: &'a SelectionSet,
+}
+
+impl<'a> Operation<'a> {
+    fn new(def: &'a OperationDefinition) -> Operation<'a> {
+        macro_rules! operation {
+            ($kind:expr, $op:expr) => {
+                Operation {
+                    kind: $kind,
+                    name: $op.name.as_ref().map(|name| name.as_str()),
+                    position: $op.position,
+                    variable_definitions: &$op.variable_definitions,
+                    directives: &$op.directives,
+                    selection_set: &$op.selection_set,
+                }
+            };
+        }
+
+        match def {
+            OperationDefinition::SelectionSet(set) => Operation {
+                kind: "query",
+                name: None,
+                position: set.span.0,
+                variable_definitions: &[],
+                directives: &[],
+                selection_set: set,
+            },
+            OperationDefinition::Query(query) => operation!("query", query),
+            OperationDefinition::Mutation(mutation) => operation!("mutation", mutation),
+            OperationDefinition::Subscription(sub) => operation!("subscription", sub),
+        }
+    }
+
+    fn location(&self) -> DirectiveLocation {
+        match self.kind {
+            "mutation" => DirectiveLocation::Mutation,
+            "subscription" => DirectiveLocation::Subscription,
+            _ => DirectiveLocation::Query,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct VariableUsage<'a> {
+    name: &'a str,
+    expected: Option<Type>,
+    has_default: bool,
+    pos: Pos,
+}
+
+/// Variables and fragments used directly by an operation or fragment definition.
+#[derive(Default)]
+struct Scope<'a> {
+    usages: Vec<VariableUsage<'a>>,
+    spreads: Vec<&'a str>,
+}
+
+struct Validator<'a> {
+    schema: &'a Schema<'a>,
+    fragments: HashMap<&'a str, &'a FragmentDefinition>,
+    fragment_scopes: HashMap<&'a str, Scope<'a>>,
+    scope: Scope<'a>,
+    errors: Vec<ValidationError>,
+}
+
+type CollectedField<'a> = (&'a str, &'a TypeInfo<'a>, &'a Field);
+
+impl<'a> Validator<'a> {
+    fn error<T: Into<String>>(&mut self, pos: Pos, message: T) {
+        self.errors.push(ValidationError::new(pos, message));
+    }
+
+    fn validate_document(&mut self, doc: &'a Document) {
+        let mut operations = Vec::new();
+        let mut fragments = Vec::new();
+        for def in &doc.definitions {
+            match def {
+                Definition::Operation(op) => operations.push(Operation::new(op)),
+                Definition::Fragment(frag) => fragments.push(frag),
+            }
+        }
+
+        let mut names = HashSet::new();
+        for op in &operations {
+            if let Some(name) = op.name {
+                if !names.insert(name) {
+                    self.error(
+                        op.position,
+                        format!("There can be only one operation named \"{}\".", name),
+                    );
+                }
+            } else if operations.len() > 1 {
+                self.error(
+                    op.position,
+                    "This anonymous operation must be the only defined operation.",
+                );
+            }
+        }
+
+        for frag in &fragments {
+            if self.fragments.contains_key(frag.name.as_str()) {
+                self.error(
+                    frag.position,
+                    format!("There can be only one fragment named \"{}\".", frag.name),
+                );
+            } else {
+                self.fragments.insert(&frag.name, frag);
+            }
+        }
+
+        for frag in &fragments {
+            self.validate_fragment(frag);
+        }
+
+        let mut used_fragments = HashSet::new();
+        for op in &operations {
+            used_fragments.extend(self.validate_operation(op));
+        }
+
+        for frag in &fragments {
+            if !used_fragments.contains(frag.name.as_str()) {
+                self.error(
+                    frag.position,
+                    format!("Fragment \"{}\" is never used.", frag.name),
+                );
+            }
+
+            if self
+                .reachable_fragments(&self.fragment_scopes[frag.name.as_str()].spreads)
+                .contains(frag.name.as_str())
+            {
+                self.error(
+                    frag.position,
+                    format!("Cannot spread fragment \"{}\" within itself.", frag.name),
+                );
+            }
+        }
+    }
+
+    fn validate_fragment(&mut self, frag: &'a FragmentDefinition) {
+        let TypeCondition::On(type_name) = &frag.type_condition;
+
+        match self.schema.type_(type_name) {
+            None => {
+                self.error(frag.position, format!("Unknown type \"{}\".", type_name));
+                self.collect_usages(&frag.selection_set);
+            }
+            Some(type_) if !type_.kind.is_composite() => {
+                self.error(
+                    frag.position,
+                    format!(
+                        "Fragment \"{}\" cannot condition on non composite type \"{}\".",
+                        frag.name, type_name
+                    ),
+                );
+                self.collect_usages(&frag.selection_set);
+            }
+            Some(type_) => self.validate_selection_set(&frag.selection_set, type_),
+        }
+
+        self.validate_directives(
+            &frag.directives,
+            DirectiveLocation::FragmentDefinition,
+            frag.position,
+        );
+
+        let scope = std::mem::take(&mut self.scope);
+        self.fragment_scopes.entry(&frag.name).or_insert(scope);
+    }
+
+    /// Returns the names of all fragments the operation uses, directly or through other fragments.
+    fn validate_operation(&mut self, op: &Operation<'a>) -> HashSet<&'a str> {
+        let root = match op.kind {
+            "mutation" => self.schema.mutation_type(),
+            "subscription" => self.schema.subscription_type(),
+            _ => self.schema.query_type(),
+        };
+
+        self.validate_variable_definitions(op.variable_definitions);
+        self.validate_directives(op.directives, op.location(), op.position);
+
+        match root {
+            None => self.error(
+                op.position,
+                format!("Schema is not configured for {}s.", op.kind),
+            ),
+            Some(root) => {
+                self.validate_selection_set(op.selection_set, root);
+
+                if op.kind == "subscription" {
+                    let mut fields = Vec::new();
+                    self.collect_fields(op.selection_set, root, &mut fields, &mut HashSet::new());
+                    let response_names = fields
+                        .iter()
+                        .map(|(name, _, _)| *name)
+                        .collect::<HashSet<_>>();
+                    if response_names.len() > 1 {
+                        self.error(
+                            op.position,
+                            match op.name {
+                                Some(name) => format!(
+                                    "Subscription \"{}\" must select only one top level field.",
+                                    name
+                                ),
+                                None => {
+                                    "Anonymous Subscription must select only one top level field."
+                                        .to_string()
+                                }
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        let scope = std::mem::take(&mut self.scope);
+        let fragments = self.reachable_fragments(&scope.spreads);
+
+        let mut usages = scope.usages;
+        for name in &fragments {
+            if let Some(frag_scope) = self.fragment_scopes.get(name) {
+                usages.extend(frag_scope.usages.iter().cloned());
+            }
+        }
+
+        let definitions = op
+            .variable_definitions
+            .iter()
+            .map(|var| (var.name.as_str(), var))
+            .collect::<HashMap<_, _>>();
+
+        for usage in &usages {
+            match definitions.get(usage.name) {
+                None => self.error(
+                    usage.pos,
+                    match op.name {
+                        Some(op_name) => format!(
+                            "Variable \"${}\" is not defined by operation \"{}\".",
+                            usage.name, op_name
+                        ),
+                        None => format!("Variable \"${}\" is not defined.", usage.name),
+                    },
+                ),
+                Some(var) => {
+                    if let Some(expected) = &usage.expected {
+                        if !self.is_variable_usage_allowed(var, expected, usage.has_default) {
+                            self.error(
+                                usage.pos,
+                                format!(
+                                    "Variable \"${}\" of type \"{}\" used in position expecting type \"{}\".",
+                                    usage.name, var.var_type, expected
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let used = usages
+            .iter()
+            .map(|usage| usage.name)
+            .collect::<HashSet<_>>();
+        for var in op.variable_definitions {
+            if !used.contains(var.name.as_str()) {
+                self.error(
+                    var.position,
+                    match op.name {
+                        Some(op_name) => format!(
+                            "Variable \"${}\" is never used in operation \"{}\".",
+                            var.name, op_name
+                        ),
+                        None => format!("Variable \"${}\" is never used.", var.name),
+                    },
+                );
+            }
+        }
+
+        fragments
+    }
+
+    fn reachable_fragments(&self, spreads: &[&'a str]) -> HashSet<&'a str> {
+        let mut visited = HashSet::new();
+        let mut stack = spreads.to_vec();
+
+        while let Some(name) = stack.pop() {
+            if visited.insert(name) {
+                if let Some(scope) = self.fragment_scopes.get(name) {
+                    stack.extend(scope.spreads.iter().cloned());
+                }
+            }
+        }
+
+        visited
+    }
+
+    fn validate_variable_definitions(&mut self, vars: &'a [VariableDefinition]) {
+        let mut seen = HashSet::new();
+        for var in vars {
+            if !seen.insert(var.name.as_str()) {
+                self.error(
+                    var.position,
+                    format!("There can be only one variable named \"${}\".", var.name),
+                );
+            }
+
+            let type_name = named_type(&var.var_type);
+            match self.schema.type_(type_name) {
+                None => self.error(var.position, format!("Unknown type \"{}\".", type_name)),
+                Some(type_) if !type_.kind.is_input() => self.error(
+                    var.position,
+                    format!(
+                        "Variable \"${}\" cannot be non-input type \"{}\".",
+                        var.name, var.var_type
+                    ),
+                ),
+                Some(_) => {
+                    if let Some(default) = &var.default_value {
+                        self.check_value(default, &var.var_type, false, var.position);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_variable_usage_allowed(
+        &self,
+        var: &VariableDefinition,
+        location: &Type,
+        location_has_default: bool,
+    ) -> bool {
+        if let Type::NonNullType(inner) = location {
+            if !is_non_null(&var.var_type) {
+                let has_non_null_default = match &var.default_value {
+                    Some(Value::Null) | None => false,
+                    Some(_) => true,
+                };
+                if !has_non_null_default && !location_has_default {
+                    return false;
+                }
+                return self.schema.is_subtype(&var.var_type, inner);
+            }
+        }
+
+        self.schema.is_subtype(&var.var_type, location)
+    }
+
+    fn validate_selection_set(&mut self, set: &'a SelectionSet, parent: &'a TypeInfo<'a>) {
+        for selection in &set.items {
+            match selection {
+                Selection::Field(field) => self.validate_field(field, parent),
+                Selection::FragmentSpread(spread) => self.validate_fragment_spread(spread, parent),
+                Selection::InlineFragment(inline_frag) => {
+                    self.validate_inline_fragment(inline_frag, parent)
+                }
+            }
+        }
+
+        self.check_field_conflicts(&[(set, parent)]);
+    }
+
+    fn validate_field(&mut self, field: &'a Field, parent: &'a TypeInfo<'a>) {
+        self.validate_directives(&field.directives, DirectiveLocation::Field, field.position);
+
+        if field.name == "__typename" {
+            self.validate_arguments(
+                &field.arguments,
+                &[],
+                field.position,
+                "field \"__typename\"",
+            );
+            if !field.selection_set.items.is_empty() {
+                self.error(
+                    field.position,
+                    "Field \"__typename\" must not have a selection since type \"String!\" has no subfields.",
+                );
+                self.collect_usages(&field.selection_set);
+            }
+            return;
+        }
+
+        let is_query_root = self
+            .schema
+            .query_type()
+            .map(|query| query.name == parent.name)
+            .unwrap_or(false);
+        if is_query_root && (field.name == "__schema" || field.name == "__type") {
+            for (_, value) in &field.arguments {
+                self.collect_variables(value, field.position);
+            }
+            self.collect_usages(&field.selection_set);
+            return;
+        }
+
+        let def = match parent.field(&field.name) {
+            Some(def) => def,
+            None => {
+                self.error(
+                    field.position,
+                    format!(
+                        "Cannot query field \"{}\" on type \"{}\".",
+                        field.name, parent.name
+                    ),
+                );
+                for (_, value) in &field.arguments {
+                    self.collect_variables(value, field.position);
+                }
+                self.collect_usages(&field.selection_set);
+                return;
+            }
+        };
+
+        self.validate_arguments(
+            &field.arguments,
+            &def.arguments,
+            field.position,
+            &format!("field \"{}.{}\"", parent.name, field.name),
+        );
+
+        match self.schema.type_(named_type(&def.field_type)) {
+            Some(type_) if type_.kind.is_leaf() && !field.selection_set.items.is_empty() => {
+                self.error(
+                    field.position,
+                    format!(
+                        "Field \"{}\" must not have a selection since type \"{}\" has no subfields.",
+                        field.name, def.field_type
+                    ),
+                );
+                self.collect_usages(&field.selection_set);
+            }
+            Some(type_) if !type_.kind.is_leaf() && field.selection_set.items.is_empty() => {
+                self.error(
+                    field.position,
+                    format!(
+                        "Field \"{}\" of type \"{}\" must have a selection of subfields. Did you mean \"{} {{ ... }}\"?",
+                        field.name, def.field_type, field.name
+                    ),
+                )
+            }
+            Some(type_) if !type_.kind.is_leaf() => {
+                self.validate_selection_set(&field.selection_set, type_)
+            }
+            Some(_) => {}
+            None => self.collect_usages(&field.selection_set),
+        }
+    }
+
+    fn validate_fragment_spread(&mut self, spread: &'a FragmentSpread, parent: &'a TypeInfo<'a>) {
+        self.validate_directives(
+            &spread.directives,
+            DirectiveLocation::FragmentSpread,
+            spread.position,
+        );
+        self.scope.spreads.push(&spread.fragment_name);
+
+        let frag = match self.fragments.get(spread.fragment_name.as_str()) {
+            Some(frag) => frag,
+            None => {
+                self.error(
+                    spread.position,
+                    format!("Unknown fragment \"{}\".", spread.fragment_name),
+                );
+                return;
+            }
+        };
+
+        let TypeCondition::On(type_name) = &frag.type_condition;
+        if let Some(frag_type) = self.schema.type_(type_name) {
+            if frag_type.kind.is_composite() && !self.can_spread(parent, frag_type) {
+                self.error(
+                    spread.position,
+                    format!(
+                        "Fragment \"{}\" cannot be spread here as objects of type \"{}\" can never be of type \"{}\".",
+                        spread.fragment_name, parent.name, frag_type.name
+                    ),
+                );
+            }
+        }
+    }
+
+    fn validate_inline_fragment(
+        &mut self,
+        inline_frag: &'a InlineFragment,
+        parent: &'a TypeInfo<'a>,
+    ) {
+        self.validate_directives(
+            &inline_frag.directives,
+            DirectiveLocation::InlineFragment,
+            inline_frag.position,
+        );
+
+        let type_ = match &inline_frag.type_condition {
+            None => parent,
+            Some(TypeCondition::On(type_name)) => match self.schema.type_(type_name) {
+                None => {
+                    self.error(
+                        inline_frag.position,
+                        format!("Unknown type \"{}\".", type_name),
+                    );
+                    self.collect_usages(&inline_frag.selection_set);
+                    return;
+                }
+                Some(type_) if !type_.kind.is_composite() => {
+                    self.error(
+                        inline_frag.position,
+                        format!(
+                            "Fragment cannot condition on non composite type \"{}\".",
+                            type_name
+                        ),
+                    );
+                    self.collect_usages(&inline_frag.selection_set);
+                    return;
+                }
+                Some(type_) => type_,
+            },
+        };
+
+        if !self.can_spread(parent, type_) {
+            self.error(
+                inline_frag.position,
+                format!(
+                    "Fragment cannot be spread here as objects of type \"{}\" can never be of type \"{}\".",
+                    parent.name, type_.name
+                ),
+            );
+        }
+
+        self.validate_selection_set(&inline_frag.selection_set, type_);
+    }
+
+    fn can_spread(&self, parent: &TypeInfo<'a>, frag_type: &TypeInfo<'a>) -> bool {
+        let parent_types = self.schema.possible_types(parent);
+        self.schema
+            .possible_types(frag_type)
+            .iter()
+            .any(|name| parent_types.contains(name))
+    }
+
+    fn validate_directives(
+        &mut self,
+        directives: &'a [Directive],
+        location: DirectiveLocation,
+        pos: Pos,
+    ) {
+        let mut seen = HashSet::new();
+        for directive in directives {
+            let def = match self.schema.directive(&directive.name) {
+                Some(def) => def,
+                None => {
+                    self.error(
+                        directive.position,
+                        format!("Unknown directive \"@{}\".", directive.name),
+                    );
+                    for (_, value) in &directive.arguments {
+                        self.collect_variables(value, pos);
+                    }
+                    continue;
+                }
+            };
+
+            if !def.locations.contains(&location) {
+                self.error(
+                    directive.position,
+                    format!(
+                        "Directive \"@{}\" may not be used on {}.",
+                        directive.name,
+                        location.as_str()
+                    ),
+                );
+            }
+
+            if !seen.insert(directive.name.as_str()) {
+                self.error(
+                    directive.position,
+                    format!(
+                        "The directive \"@{}\" can only be used once at this location.",
+                        directive.name
+                    ),
+                );
+            }
+
+            self.validate_arguments(
+                &directive.arguments,
+                &def.arguments,
+                directive.position,
+                &format!("directive \"@{}\"", directive.name),
+            );
+        }
+    }
+
+    fn validate_arguments(
+        &mut self,
+        args: &'a [(String, Value)],
+        defs: &'a [InputValue],
+        pos: Pos,
+        owner: &str,
+    ) {
+        let mut seen = HashSet::new();
+        for (name, value) in args {
+            if !seen.insert(name.as_str()) {
+                self.error(
+                    pos,
+                    format!("There can be only one argument named \"{}\".", name),
+                );
+            }
+
+            match defs.iter().find(|def| def.name == *name) {
+                Some(def) => {
+                    self.check_value(value, &def.value_type, def.default_value.is_some(), pos)
+                }
+                None => {
+                    self.error(pos, format!("Unknown argument \"{}\" on {}.", name, owner));
+                    self.collect_variables(value, pos);
+                }
+            }
+        }
+
+        for def in defs {
+            let provided = args.iter().any(|(name, _)| *name == def.name);
+            if !provided && is_non_null(&def.value_type) && def.default_value.is_none() {
+                self.error(
+                    pos,
+                    format!(
+                        "Argument \"{}\" of required type \"{}\" was not provided on {}.",
+                        def.name, def.value_type, owner
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_value(&mut self, value: &'a Value, expected: &Type, has_default: bool, pos: Pos) {
+        match (value, expected) {
+            (Value::Variable(name), _) => self.scope.usages.push(VariableUsage {
+                name,
+                expected: Some(expected.clone()),
+                has_default,
+                pos,
+            }),
+            (Value::Null, Type::NonNullType(_)) => self.error(
+                pos,
+                format!("Expected value of type \"{}\", found null.", expected),
+            ),
+            (_, Type::NonNullType(inner)) => self.check_value(value, inner, false, pos),
+            (Value::Null, _) => {}
+            (Value::List(items), Type::ListType(inner)) => {
+                for item in items {
+                    self.check_value(item, inner, false, pos);
+                }
+            }
+            (_, Type::ListType(inner)) => self.check_value(value, inner, false, pos),
+            (_, Type::NamedType(name)) => self.check_named_value(value, name, pos),
+        }
+    }
+
+    fn check_named_value(&mut self, value: &'a Value, type_name: &str, pos: Pos) {
+        let type_ = match self.schema.type_(type_name) {
+            Some(type_) => type_,
+            None => return,
+        };
+
+        let valid = match type_.kind {
+            TypeKind::InputObject => match value {
+                Value::Object(fields) => {
+                    for (name, field_value) in fields {
+                        match type_.input_field(name) {
+                            Some(def) => self.check_value(
+                                field_value,
+                                &def.value_type,
+                                def.default_value.is_some(),
+                                pos,
+                            ),
+                            None => {
+                                self.error(
+                                    pos,
+                                    format!(
+                                        "Field \"{}\" is not defined by type \"{}\".",
+                                        name, type_.name
+                                    ),
+                                );
+                                self.collect_variables(field_value, pos);
+                            }
+                        }
+                    }
+
+                    for def in &type_.input_fields {
+                        if !fields.contains_key(&def.name)
+                            && is_non_null(&def.value_type)
+                            && def.default_value.is_none()
+                        {
+                            self.error(
+                                pos,
+                                format!(
+                                    "Field \"{}.{}\" of required type \"{}\" was not provided.",
+                                    type_.name, def.name, def.value_type
+                                ),
+                            );
+                        }
+                    }
+
+                    true
+                }
+                _ => false,
+            },
+            TypeKind::Enum => match value {
+                Value::Enum(name) => type_.values.iter().any(|value| value.name == *name),
+                _ => false,
+            },
+            TypeKind::Scalar => match (type_name, value) {
+                ("Int", Value::Int(number)) => number
+                    .as_i64()
+                    .map(|n| n >= i64::from(i32::MIN) && n <= i64::from(i32::MAX))
+                    .unwrap_or(false),
+                ("Float", Value::Int(_)) | ("Float", Value::Float(_)) => true,
+                ("String", Value::String(_)) => true,
+                ("Boolean", Value::Boolean(_)) => true,
+                ("ID", Value::String(_)) | ("ID", Value::Int(_)) => true,
+                ("Int", _) | ("Float", _) | ("String", _) | ("Boolean", _) | ("ID", _) => false,
+                _ => {
+                    self.collect_variables(value, pos);
+                    true
+                }
+            },
+            TypeKind::Object | TypeKind::Interface | TypeKind::Union => true,
+        };
+
+        if !valid {
+            self.error(
+                pos,
+                format!(
+                    "Expected value of type \"{}\", found {}.",
+                    type_.name, value
+                ),
+            );
+        }
+    }
+
+    /// Record the variables and fragments used in a selection set that can't be validated, like
+    /// that of an unknown field, so that they don't look unused.
+    fn collect_usages(&mut self, set: &'a SelectionSet) {
+        for selection in &set.items {
+            match selection {
+                Selection::Field(field) => {
+                    for (_, value) in &field.arguments {
+                        self.collect_variables(value, field.position);
+                    }
+                    self.collect_directive_variables(&field.directives, field.position);
+                    self.collect_usages(&field.selection_set);
+                }
+                Selection::FragmentSpread(spread) => {
+                    self.collect_directive_variables(&spread.directives, spread.position);
+                    self.scope.spreads.push(&spread.fragment_name);
+                }
+                Selection::InlineFragment(inline_frag) => {
+                    let pos = inline_frag.position;
+                    self.collect_directive_variables(&inline_frag.directives, pos);
+                    self.collect_usages(&inline_frag.selection_set);
+                }
+            }
+        }
+    }
+
+    fn collect_directive_variables(&mut self, directives: &'a [Directive], pos: Pos) {
+        for directive in directives {
+            for (_, value) in &directive.arguments {
+                self.collect_variables(value, pos);
+            }
+        }
+    }
+
+    /// Record variables used in a value whose expected type is unknown.
+    fn collect_variables(&mut self, value: &'a Value, pos: Pos) {
+        match value {
+            Value::Variable(name) => self.scope.usages.push(VariableUsage {
+                name,
+                expected: None,
+                has_default: false,
+                pos,
+            }),
+            Value::List(items) => {
+                for item in items {
+                    self.collect_variables(item, pos);
+                }
+            }
+            Value::Object(fields) => {
+                for value in fields.values() {
+                    self.collect_variables(value, pos);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_fields(
+        &self,
+        set: &'a SelectionSet,
+        parent: &'a TypeInfo<'a>,
+        out: &mut Vec<CollectedField<'a>>,
+        visited: &mut HashSet<&'a str>,
+    ) {
+        for selection in &set.items {
+            match selection {
+                Selection::Field(field) => {
+                    let response_name = field.alias.as_ref().unwrap_or(&field.name);
+                    out.push((response_name, parent, field));
+                }
+                Selection::InlineFragment(inline_frag) => {
+                    let type_ = match &inline_frag.type_condition {
+                        None => Some(parent),
+                        Some(TypeCondition::On(name)) => self.schema.type_(name),
+                    };
+                    if let Some(type_) = type_ {
+                        self.collect_fields(&inline_frag.selection_set, type_, out, visited);
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    if !visited.insert(&spread.fragment_name) {
+                        continue;
+                    }
+                    if let Some(frag) = self.fragments.get(spread.fragment_name.as_str()) {
+                        let TypeCondition::On(name) = &frag.type_condition;
+                        if let Some(type_) = self.schema.type_(name) {
+                            self.collect_fields(&frag.selection_set, type_, out, visited);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fields with the same response name must be mergeable into one field.
+    fn check_field_conflicts(&mut self, sets: &[(&'a SelectionSet, &'a TypeInfo<'a>)]) {
+        let mut fields = Vec::new();
+        let mut visited = HashSet::new();
+        for (set, parent) in sets {
+            self.collect_fields(set, parent, &mut fields, &mut visited);
+        }
+
+        let mut groups: Vec<(&str, Vec<(&'a TypeInfo<'a>, &'a Field)>)> = Vec::new();
+        for (response_name, parent, field) in fields {
+            match groups.iter_mut().find(|(name, _)| *name == response_name) {
+                Some((_, group)) => group.push((parent, field)),
+                None => groups.push((response_name, vec![(parent, field)])),
+            }
+        }
+
+        for (response_name, group) in groups {
+            if group.len() < 2 {
+                continue;
+            }
+
+            let (first_parent, first) = group[0];
+            for &(parent, field) in &group[1..] {
+                if let Some(reason) = self.field_conflict(first_parent, first, parent, field) {
+                    self.error(
+                        field.position,
+                        format!(
+                            "Fields \"{}\" conflict because {}. Use different aliases on the fields to fetch both if this was intentional.",
+                            response_name, reason
+                        ),
+                    );
+                }
+            }
+
+            let subselections = group
+                .iter()
+                .filter(|(_, field)| !field.selection_set.items.is_empty())
+                .filter_map(|(parent, field)| {
+                    let def = parent.field(&field.name)?;
+                    let type_ = self.schema.type_(named_type(&def.field_type))?;
+                    Some((&field.selection_set, type_))
+                })
+                .collect::<Vec<_>>();
+            if subselections.len() > 1 {
+                self.check_field_conflicts(&subselections);
+            }
+        }
+    }
+
+    fn field_conflict(
+        &self,
+        parent_a: &TypeInfo<'a>,
+        a: &Field,
+        parent_b: &TypeInfo<'a>,
+        b: &Field,
+    ) -> Option<String> {
+        let parents_overlap = parent_a.name == parent_b.name
+            || parent_a.kind != TypeKind::Object
+            || parent_b.kind != TypeKind::Object;
+
+        if parents_overlap {
+            if a.name != b.name {
+                return Some(format!(
+                    "\"{}\" and \"{}\" are different fields",
+                    a.name, b.name
+                ));
+            }
+
+            if !same_arguments(&a.arguments, &b.arguments) {
+                return Some("they have differing arguments".to_string());
+            }
+        }
+
+        let type_a = parent_a.field(&a.name).map(|def| &def.field_type);
+        let type_b = parent_b.field(&b.name).map(|def| &def.field_type);
+        if let (Some(type_a), Some(type_b)) = (type_a, type_b) {
+            if self.types_conflict(type_a, type_b) {
+                return Some(format!(
+                    "they return conflicting types \"{}\" and \"{}\"",
+                    type_a, type_b
+                ));
+            }
+        }
+
+        None
+    }
+
+    fn types_conflict(&self, a: &Type, b: &Type) -> bool {
+        match (a, b) {
+            (Type::NonNullType(a), Type::NonNullType(b))
+            | (Type::ListType(a), Type::ListType(b)) => self.types_conflict(a, b),
+            (Type::NamedType(a), Type::NamedType(b)) => {
+                let leaf = |name: &str| {
+                    self.schema
+                        .type_(name)
+                        .map(|type_| type_.kind.is_leaf())
+                        .unwrap_or(false)
+                };
+                (leaf(a) || leaf(b)) && a != b
+            }
+            _ => true,
+        }
+    }
+}
+
+fn same_arguments(a: &[(String, Value)], b: &[(String, Value)]) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(name, value)| {
+            b.iter()
+                .any(|(other_name, other_value)| name == other_name && value == other_value)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::parse::{parse_query, parse_schema};
+
+    const SCHEMA: &str = "
+schema { query: Query mutation: Mutation subscription: Subscription }
+
+type Query {
+  user(id: ID!): User
+  users(filter: UserFilter, first: Int = 10): [User!]!
+  node(id: ID!): Node
+  search(query: String!): [SearchResult!]!
+}
+
+type Mutation { createUser(name: String!, role: Role = USER): User }
+type Subscription { userCreated: User teamCreated: Team }
+
+interface Node { id: ID! }
+
+type User implements Node { id: ID! name: String role: Role team: Team }
+type Team implements Node { id: ID! name: String members: [User!]! }
+
+union SearchResult = User | Team
+
+enum Role { ADMIN USER }
+
+input UserFilter { role: Role name: String query: String! }
+    ";
+
+    fn errors(query: &str) -> Vec<String> {
+        let schema_doc = parse_schema(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_doc);
+        let doc = parse_query(query.trim()).unwrap();
+        validate(&doc, &schema)
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let query = "
+query User($id: ID!, $withTeam: Boolean = false) {
+  user(id: $id) { ...userFields team @include(if: $withTeam) { name } }
+}
+query Search($filter: UserFilter) {
+  users(filter: $filter) { id __typename }
+  search(query: \"bob\") { ... on User { name } ... on Team { name members { id } } }
+  node(id: 1) { id ... on User { role } }
+}
+mutation Create { createUser(name: \"Bob\", role: ADMIN) { id } }
+subscription Created { userCreated { id } }
+fragment userFields on User { id name }
+        ";
+
+        assert_eq!(errors(query), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_unknown_fields_and_leafs() {
+        let query = "
+query One { user(id: 1) { id email team } }
+query Two { users { name { first } } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:30: Cannot query field \"email\" on type \"User\".",
+                "1:36: Field \"team\" of type \"Team\" must have a selection of subfields. Did you mean \"team { ... }\"?",
+                "2:21: Field \"name\" must not have a selection since type \"String\" has no subfields.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arguments() {
+        let query = "
+query One { user { id } users(first: \"ten\", after: 1) { id } }
+query Two { users(filter: { role: OWNER, bogus: 1 }) { id } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:13: Argument \"id\" of required type \"ID!\" was not provided on field \"Query.user\".",
+                "1:25: Expected value of type \"Int\", found \"ten\".",
+                "1:25: Unknown argument \"after\" on field \"Query.users\".",
+                "2:13: Expected value of type \"Role\", found OWNER.",
+                "2:13: Field \"UserFilter.query\" of required type \"String!\" was not provided.",
+                "2:13: Field \"bogus\" is not defined by type \"UserFilter\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragments() {
+        let query = "
+query One { user(id: 1) { ...missing ...teamFields ... on Role { id } } }
+fragment teamFields on Team { name }
+fragment unused on User { id }
+fragment onScalar on String { id }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:30: Unknown fragment \"missing\".",
+                "1:41: Fragment \"teamFields\" cannot be spread here as objects of type \"User\" can never be of type \"Team\".",
+                "1:56: Fragment cannot condition on non composite type \"Role\".",
+                "3:1: Fragment \"unused\" is never used.",
+                "4:1: Fragment \"onScalar\" cannot condition on non composite type \"String\".",
+                "4:1: Fragment \"onScalar\" is never used.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usages_below_errors() {
+        let query = "
+query C($id: ID!, $role: Role) { typo(id: $id) { ...U } }
+query D($first: Int) { user(id: 1) { id { ...T } ... on Missing { ...V @include(if: $x) } } }
+query E($name: String!) { __type(name: $name) { name } }
+fragment U on User { id role(is: $role) }
+fragment T on User { id }
+fragment V on Missing { ...W }
+fragment W on User { team { members(first: $first) } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:34: Cannot query field \"typo\" on type \"Query\".",
+                "2:38: Field \"id\" must not have a selection since type \"ID!\" has no subfields.",
+                "2:54: Unknown type \"Missing\".",
+                "2:70: Variable \"$x\" is not defined by operation \"D\".",
+                "4:25: Unknown argument \"is\" on field \"User.role\".",
+                "6:1: Unknown type \"Missing\".",
+                "7:29: Field \"members\" of type \"[User!]!\" must have a selection of subfields. Did you mean \"members { ... }\"?",
+                "7:29: Unknown argument \"first\" on field \"Team.members\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragment_cycles() {
+        let query = "
+query One { user(id: 1) { ...a } }
+fragment a on User { ...b }
+fragment b on User { ...a }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "2:1: Cannot spread fragment \"a\" within itself.",
+                "3:1: Cannot spread fragment \"b\" within itself.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let query = "
+query One($id: ID, $id: ID, $user: User, $unused: Int) { user(id: $id) { id } }
+query Two($first: String) { users(first: $first) { ...withFilter } }
+fragment withFilter on User { team { members { name } } id @skip(if: $skip) }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:20: There can be only one variable named \"$id\".",
+                "1:29: Variable \"$user\" cannot be non-input type \"User\".",
+                "1:29: Variable \"$user\" is never used in operation \"One\".",
+                "1:42: Variable \"$unused\" is never used in operation \"One\".",
+                "1:58: Variable \"$id\" of type \"ID\" used in position expecting type \"ID!\".",
+                "2:29: Variable \"$first\" of type \"String\" used in position expecting type \"Int\".",
+                "3:60: Variable \"$skip\" is not defined by operation \"Two\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operations() {
+        let query = "
+query One { user(id: 1) { id } }
+query One { user(id: 2) { id } }
+{ users { id } }
+subscription { userCreated { id } teamCreated { id } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "2:1: There can be only one operation named \"One\".",
+                "3:1: This anonymous operation must be the only defined operation.",
+                "4:1: Anonymous Subscription must select only one top level field.",
+                "4:1: This anonymous operation must be the only defined operation.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directives() {
+        let query = "
+query One @skip(if: true) { user(id: 1) { id @include(if: true) @include(if: false) name @unknown } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:11: Directive \"@skip\" may not be used on QUERY.",
+                "1:65: The directive \"@include\" can only be used once at this location.",
+                "1:90: Unknown directive \"@unknown\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_conflicts() {
+        let query = "
+query One { user(id: 1) { name: id name } users { id } users(first: 1) { id } }
+        ";
+
+        assert_eq!(
+            errors(query),
+            vec![
+                "1:36: Fields \"name\" conflict because \"id\" and \"name\" are different fields. Use different aliases on the fields to fetch both if this was intentional.",
+                "1:56: Fields \"users\" conflict because they have differing arguments. Use different aliases on the fields to fetch both if this was intentional.",
+            ]
+        );
+    }
+}
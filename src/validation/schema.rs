@@ -0,0 +1,969 @@
+use super::{sort_errors, ValidationError};
+use crate::ast::schema::*;
+use crate::format::parse::parse_schema;
+use graphql_parser::Pos;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+
+lazy_static! {
+    static ref BUILTINS: Document = parse_schema(
+        "
+scalar Int
+scalar Float
+scalar String
+scalar Boolean
+scalar ID
+
+directive @skip(if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+directive @include(if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+directive @deprecated(reason: String = \"No longer supported\") on FIELD_DEFINITION | ENUM_VALUE
+        "
+    )
+    .unwrap();
+}
+
+/// Validate a schema document against the type system rules from the spec.
+///
+/// All errors are returned, ordered by their position in the document.
+pub fn validate(doc: &Document) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let schema = Schema::build(doc, &mut errors);
+
+    schema.validate_root_types(&mut errors);
+
+    for name in &schema.user_types {
+        let type_ = &schema.types[name];
+        check_name(type_.name, type_.position, &mut errors);
+
+        match type_.kind {
+            TypeKind::Scalar => {}
+            TypeKind::Object => {
+                schema.validate_fields(type_, &mut errors);
+                schema.validate_interfaces(type_, &mut errors);
+            }
+            TypeKind::Interface => schema.validate_fields(type_, &mut errors),
+            TypeKind::Union => schema.validate_union(type_, &mut errors),
+            TypeKind::Enum => validate_enum(type_, &mut errors),
+            TypeKind::InputObject => schema.validate_input_object(type_, &mut errors),
+        }
+    }
+
+    for def in &doc.definitions {
+        if let Definition::DirectiveDefinition(directive) = def {
+            check_name(&directive.name, directive.position, &mut errors);
+            schema.validate_arguments(
+                &directive.arguments,
+                &format!("@{}", directive.name),
+                &mut errors,
+            );
+        }
+    }
+
+    sort_errors(&mut errors);
+    errors
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+}
+
+impl TypeKind {
+    pub fn is_input(self) -> bool {
+        match self {
+            TypeKind::Scalar | TypeKind::Enum | TypeKind::InputObject => true,
+            TypeKind::Object | TypeKind::Interface | TypeKind::Union => false,
+        }
+    }
+
+    pub fn is_output(self) -> bool {
+        self != TypeKind::InputObject
+    }
+
+    pub fn is_leaf(self) -> bool {
+        self == TypeKind::Scalar || self == TypeKind::Enum
+    }
+
+    pub fn is_composite(self) -> bool {
+        match self {
+            TypeKind::Object | TypeKind::Interface | TypeKind::Union => true,
+            TypeKind::Scalar | TypeKind::Enum | TypeKind::InputObject => false,
+        }
+    }
+
+    pub fn is_abstract(self) -> bool {
+        self == TypeKind::Interface || self == TypeKind::Union
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            TypeKind::Scalar => "scalar",
+            TypeKind::Object => "object",
+            TypeKind::Interface => "interface",
+            TypeKind::Union => "union",
+            TypeKind::Enum => "enum",
+            TypeKind::InputObject => "input object",
+        }
+    }
+}
+
+/// A named type with all of its extensions merged in.
+#[derive(Debug)]
+pub struct TypeInfo<'a> {
+    pub name: &'a str,
+    pub kind: TypeKind,
+    pub position: Pos,
+    pub fields: Vec<&'a Field>,
+    pub interfaces: Vec<&'a str>,
+    pub members: Vec<&'a str>,
+    pub values: Vec<&'a EnumValue>,
+    pub input_fields: Vec<&'a InputValue>,
+}
+
+impl<'a> TypeInfo<'a> {
+    fn new(name: &'a str, kind: TypeKind, position: Pos) -> TypeInfo<'a> {
+        TypeInfo {
+            name,
+            kind,
+            position,
+            fields: Vec::new(),
+            interfaces: Vec::new(),
+            members: Vec::new(),
+            values: Vec::new(),
+            input_fields: Vec::new(),
+        }
+    }
+
+    fn from_definition(def: &'a TypeDefinition) -> TypeInfo<'a> {
+        match def {
+            TypeDefinition::Scalar(scalar) => {
+                TypeInfo::new(&scalar.name, TypeKind::Scalar, scalar.position)
+            }
+            TypeDefinition::Object(obj) => {
+                let mut info = TypeInfo::new(&obj.name, TypeKind::Object, obj.position);
+                info.fields.extend(obj.fields.iter());
+                info.interfaces
+                    .extend(obj.implements_interfaces.iter().map(|name| name.as_str()));
+                info
+            }
+            TypeDefinition::Interface(interface) => {
+                let mut info =
+                    TypeInfo::new(&interface.name, TypeKind::Interface, interface.position);
+                info.fields.extend(interface.fields.iter());
+                info
+            }
+            TypeDefinition::Union(union) => {
+                let mut info = TypeInfo::new(&union.name, TypeKind::Union, union.position);
+                info.members
+                    .extend(union.types.iter().map(|name| name.as_str()));
+                info
+            }
+            TypeDefinition::Enum(enum_) => {
+                let mut info = TypeInfo::new(&enum_.name, TypeKind::Enum, enum_.position);
+                info.values.extend(enum_.values.iter());
+                info
+            }
+            TypeDefinition::InputObject(obj) => {
+                let mut info = TypeInfo::new(&obj.name, TypeKind::InputObject, obj.position);
+                info.input_fields.extend(obj.fields.iter());
+                info
+            }
+        }
+    }
+
+    fn extend(&mut self, ext: &'a TypeExtension) {
+        match ext {
+            TypeExtension::Scalar(_) => {}
+            TypeExtension::Object(obj) => {
+                self.fields.extend(obj.fields.iter());
+                self.interfaces
+                    .extend(obj.implements_interfaces.iter().map(|name| name.as_str()));
+            }
+            TypeExtension::Interface(interface) => self.fields.extend(interface.fields.iter()),
+            TypeExtension::Union(union) => self
+                .members
+                .extend(union.types.iter().map(|name| name.as_str())),
+            TypeExtension::Enum(enum_) => self.values.extend(enum_.values.iter()),
+            TypeExtension::InputObject(obj) => self.input_fields.extend(obj.fields.iter()),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&'a Field> {
+        self.fields.iter().find(|field| field.name == name).cloned()
+    }
+
+    pub fn input_field(&self, name: &str) -> Option<&'a InputValue> {
+        self.input_fields
+            .iter()
+            .find(|field| field.name == name)
+            .cloned()
+    }
+}
+
+/// An index over a schema document, including the built-in scalars and directives.
+pub struct Schema<'a> {
+    types: HashMap<&'a str, TypeInfo<'a>>,
+    user_types: Vec<&'a str>,
+    directives: HashMap<&'a str, &'a DirectiveDefinition>,
+    schema_def: Option<&'a SchemaDefinition>,
+}
+
+impl<'a> Schema<'a> {
+    pub fn new(doc: &'a Document) -> Schema<'a> {
+        Schema::build(doc, &mut Vec::new())
+    }
+
+    fn build(doc: &'a Document, errors: &mut Vec<ValidationError>) -> Schema<'a> {
+        let mut schema = Schema {
+            types: HashMap::new(),
+            user_types: Vec::new(),
+            directives: HashMap::new(),
+            schema_def: None,
+        };
+
+        for def in &doc.definitions {
+            match def {
+                Definition::SchemaDefinition(schema_def) => {
+                    if schema.schema_def.is_some() {
+                        errors.push(ValidationError::new(
+                            schema_def.position,
+                            "Must provide only one schema definition.",
+                        ));
+                    } else {
+                        schema.schema_def = Some(schema_def);
+                    }
+                }
+                Definition::TypeDefinition(type_def) => {
+                    let info = TypeInfo::from_definition(type_def);
+                    if schema.types.contains_key(info.name) {
+                        errors.push(ValidationError::new(
+                            info.position,
+                            format!("There can be only one type named \"{}\".", info.name),
+                        ));
+                    } else {
+                        schema.user_types.push(info.name);
+                        schema.types.insert(info.name, info);
+                    }
+                }
+                Definition::DirectiveDefinition(directive) => {
+                    if schema.directives.contains_key(directive.name.as_str()) {
+                        errors.push(ValidationError::new(
+                            directive.position,
+                            format!(
+                                "There can be only one directive named \"@{}\".",
+                                directive.name
+                            ),
+                        ));
+                    } else {
+                        schema.directives.insert(&directive.name, directive);
+                    }
+                }
+                Definition::TypeExtension(_) => {}
+            }
+        }
+
+        for def in &doc.definitions {
+            if let Definition::TypeExtension(ext) = def {
+                let (name, kind, position) = extension_info(ext);
+                match schema.types.get_mut(name) {
+                    Some(ref mut info) if info.kind == kind => info.extend(ext),
+                    Some(info) => errors.push(ValidationError::new(
+                        position,
+                        format!(
+                            "Cannot extend non-{} type \"{}\".",
+                            kind.describe(),
+                            info.name
+                        ),
+                    )),
+                    None => errors.push(ValidationError::new(
+                        position,
+                        format!("Cannot extend type \"{}\" because it is not defined.", name),
+                    )),
+                }
+            }
+        }
+
+        for def in &BUILTINS.definitions {
+            match def {
+                Definition::TypeDefinition(type_def) => {
+                    let info = TypeInfo::from_definition(type_def);
+                    schema.types.entry(info.name).or_insert(info);
+                }
+                Definition::DirectiveDefinition(directive) => {
+                    schema
+                        .directives
+                        .entry(directive.name.as_str())
+                        .or_insert(directive);
+                }
+                _ => {}
+            }
+        }
+
+        schema
+    }
+
+    pub fn type_(&self, name: &str) -> Option<&TypeInfo<'a>> {
+        self.types.get(name)
+    }
+
+    pub fn directive(&self, name: &str) -> Option<&'a DirectiveDefinition> {
+        self.directives.get(name).cloned()
+    }
+
+    pub fn query_type(&self) -> Option<&TypeInfo<'a>> {
+        self.root_type_name(|def| &def.query, "Query")
+            .and_then(|name| self.type_(name))
+    }
+
+    pub fn mutation_type(&self) -> Option<&TypeInfo<'a>> {
+        self.root_type_name(|def| &def.mutation, "Mutation")
+            .and_then(|name| self.type_(name))
+    }
+
+    pub fn subscription_type(&self) -> Option<&TypeInfo<'a>> {
+        self.root_type_name(|def| &def.subscription, "Subscription")
+            .and_then(|name| self.type_(name))
+    }
+
+    fn root_type_name<F>(&self, get: F, default: &'a str) -> Option<&'a str>
+    where
+        F: Fn(&'a SchemaDefinition) -> &'a Option<String>,
+    {
+        match self.schema_def {
+            Some(def) => get(def).as_ref().map(|name| name.as_str()),
+            None => Some(default),
+        }
+    }
+
+    /// Whether `object` is one of the concrete types of the abstract type `abstract_`.
+    pub fn is_possible_type(&self, abstract_: &TypeInfo, object: &str) -> bool {
+        match abstract_.kind {
+            TypeKind::Union => abstract_.members.contains(&object),
+            TypeKind::Interface => self
+                .type_(object)
+                .map(|object| object.interfaces.contains(&abstract_.name))
+                .unwrap_or(false),
+            _ => abstract_.name == object,
+        }
+    }
+
+    /// The object types a value of the given composite type can be at runtime.
+    pub fn possible_types(&self, type_: &TypeInfo<'a>) -> HashSet<&'a str> {
+        match type_.kind {
+            TypeKind::Object => Some(type_.name).into_iter().collect(),
+            TypeKind::Union => type_.members.iter().cloned().collect(),
+            TypeKind::Interface => self
+                .types
+                .values()
+                .filter(|object| {
+                    object.kind == TypeKind::Object && object.interfaces.contains(&type_.name)
+                })
+                .map(|object| object.name)
+                .collect(),
+            _ => HashSet::new(),
+        }
+    }
+
+    /// Whether a value of type `sub` can be used where `super_` is expected.
+    pub fn is_subtype(&self, sub: &Type, super_: &Type) -> bool {
+        match (sub, super_) {
+            (Type::NonNullType(sub), Type::NonNullType(super_)) => self.is_subtype(sub, super_),
+            (Type::NonNullType(sub), _) => self.is_subtype(sub, super_),
+            (_, Type::NonNullType(_)) => false,
+            (Type::ListType(sub), Type::ListType(super_)) => self.is_subtype(sub, super_),
+            (Type::ListType(_), _) | (_, Type::ListType(_)) => false,
+            (Type::NamedType(sub), Type::NamedType(super_)) => {
+                sub == super_
+                    || self
+                        .type_(super_)
+                        .map(|super_| {
+                            super_.kind.is_abstract() && self.is_possible_type(super_, sub)
+                        })
+                        .unwrap_or(false)
+            }
+        }
+    }
+
+    fn validate_root_types(&self, errors: &mut Vec<ValidationError>) {
+        let position = self
+            .schema_def
+            .map(|def| def.position)
+            .unwrap_or(Pos { line: 1, column: 1 });
+
+        match self.root_type_name(|def| &def.query, "Query") {
+            Some(name) => self.validate_root_type("Query", name, position, errors),
+            None => errors.push(ValidationError::new(
+                position,
+                "Query root type must be provided.",
+            )),
+        }
+
+        for (operation, name) in &[
+            (
+                "Mutation",
+                self.root_type_name(|def| &def.mutation, "Mutation"),
+            ),
+            (
+                "Subscription",
+                self.root_type_name(|def| &def.subscription, "Subscription"),
+            ),
+        ] {
+            match name {
+                Some(name) if self.schema_def.is_some() || self.types.contains_key(name) => {
+                    self.validate_root_type(operation, name, position, errors)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn validate_root_type(
+        &self,
+        operation: &str,
+        name: &str,
+        position: Pos,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        match self.type_(name) {
+            Some(type_) if type_.kind == TypeKind::Object => {}
+            Some(type_) => errors.push(ValidationError::new(
+                position,
+                format!(
+                    "{} root type must be Object type, it cannot be {}.",
+                    operation, type_.name
+                ),
+            )),
+            None if operation == "Query" && self.schema_def.is_none() => errors.push(
+                ValidationError::new(position, "Query root type must be provided."),
+            ),
+            None => errors.push(ValidationError::new(
+                position,
+                format!("Unknown type \"{}\".", name),
+            )),
+        }
+    }
+
+    fn check_type_reference(
+        &self,
+        type_: &Type,
+        position: Pos,
+        errors: &mut Vec<ValidationError>,
+    ) -> Option<&TypeInfo<'a>> {
+        let name = named_type(type_);
+        let found = self.type_(name);
+        if found.is_none() {
+            errors.push(ValidationError::new(
+                position,
+                format!("Unknown type \"{}\".", name),
+            ));
+        }
+        found
+    }
+
+    fn validate_fields(&self, type_: &TypeInfo<'a>, errors: &mut Vec<ValidationError>) {
+        if type_.fields.is_empty() {
+            errors.push(ValidationError::new(
+                type_.position,
+                format!("Type {} must define one or more fields.", type_.name),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for field in &type_.fields {
+            check_name(&field.name, field.position, errors);
+
+            if !seen.insert(field.name.as_str()) {
+                errors.push(ValidationError::new(
+                    field.position,
+                    format!(
+                        "Field \"{}.{}\" can only be defined once.",
+                        type_.name, field.name
+                    ),
+                ));
+            }
+
+            if let Some(field_type) =
+                self.check_type_reference(&field.field_type, field.position, errors)
+            {
+                if !field_type.kind.is_output() {
+                    errors.push(ValidationError::new(
+                        field.position,
+                        format!(
+                            "The type of {}.{} must be Output Type but got: {}.",
+                            type_.name, field.name, field.field_type
+                        ),
+                    ));
+                }
+            }
+
+            self.validate_arguments(
+                &field.arguments,
+                &format!("{}.{}", type_.name, field.name),
+                errors,
+            );
+        }
+    }
+
+    fn validate_arguments(
+        &self,
+        arguments: &[InputValue],
+        owner: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let mut seen = HashSet::new();
+        for arg in arguments {
+            check_name(&arg.name, arg.position, errors);
+
+            if !seen.insert(arg.name.as_str()) {
+                errors.push(ValidationError::new(
+                    arg.position,
+                    format!(
+                        "Argument \"{}({}:)\" can only be defined once.",
+                        owner, arg.name
+                    ),
+                ));
+            }
+
+            if let Some(arg_type) = self.check_type_reference(&arg.value_type, arg.position, errors)
+            {
+                if !arg_type.kind.is_input() {
+                    errors.push(ValidationError::new(
+                        arg.position,
+                        format!(
+                            "The type of {}({}:) must be Input Type but got: {}.",
+                            owner, arg.name, arg.value_type
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_interfaces(&self, object: &TypeInfo<'a>, errors: &mut Vec<ValidationError>) {
+        let mut seen = HashSet::new();
+        for name in &object.interfaces {
+            if !seen.insert(name) {
+                errors.push(ValidationError::new(
+                    object.position,
+                    format!("Type {} can only implement {} once.", object.name, name),
+                ));
+                continue;
+            }
+
+            match self.type_(name) {
+                Some(interface) if interface.kind == TypeKind::Interface => {
+                    self.validate_implementation(object, interface, errors)
+                }
+                Some(_) => errors.push(ValidationError::new(
+                    object.position,
+                    format!(
+                        "Type {} must only implement Interface types, it cannot implement {}.",
+                        object.name, name
+                    ),
+                )),
+                None => errors.push(ValidationError::new(
+                    object.position,
+                    format!("Unknown type \"{}\".", name),
+                )),
+            }
+        }
+    }
+
+    fn validate_implementation(
+        &self,
+        object: &TypeInfo<'a>,
+        interface: &TypeInfo<'a>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for interface_field in &interface.fields {
+            let object_field = match object.field(&interface_field.name) {
+                Some(field) => field,
+                None => {
+                    errors.push(ValidationError::new(
+                        object.position,
+                        format!(
+                            "Interface field {}.{} expected but {} does not provide it.",
+                            interface.name, interface_field.name, object.name
+                        ),
+                    ));
+                    continue;
+                }
+            };
+
+            if !self.is_subtype(&object_field.field_type, &interface_field.field_type) {
+                errors.push(ValidationError::new(
+                    object_field.position,
+                    format!(
+                        "Interface field {}.{} expects type {} but {}.{} is type {}.",
+                        interface.name,
+                        interface_field.name,
+                        interface_field.field_type,
+                        object.name,
+                        object_field.name,
+                        object_field.field_type
+                    ),
+                ));
+            }
+
+            for interface_arg in &interface_field.arguments {
+                match object_field
+                    .arguments
+                    .iter()
+                    .find(|arg| arg.name == interface_arg.name)
+                {
+                    None => errors.push(ValidationError::new(
+                        object_field.position,
+                        format!(
+                            "Interface field argument {}.{}({}:) expected but {}.{} does not provide it.",
+                            interface.name,
+                            interface_field.name,
+                            interface_arg.name,
+                            object.name,
+                            object_field.name
+                        ),
+                    )),
+                    Some(object_arg) if object_arg.value_type != interface_arg.value_type => {
+                        errors.push(ValidationError::new(
+                            object_arg.position,
+                            format!(
+                                "Interface field argument {}.{}({}:) expects type {} but {}.{}({}:) is type {}.",
+                                interface.name,
+                                interface_field.name,
+                                interface_arg.name,
+                                interface_arg.value_type,
+                                object.name,
+                                object_field.name,
+                                object_arg.name,
+                                object_arg.value_type
+                            ),
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for object_arg in &object_field.arguments {
+                let in_interface = interface_field
+                    .arguments
+                    .iter()
+                    .any(|arg| arg.name == object_arg.name);
+                if !in_interface && is_non_null(&object_arg.value_type) {
+                    errors.push(ValidationError::new(
+                        object_arg.position,
+                        format!(
+                            "Object field {}.{} includes required argument {} that is missing from the Interface field {}.{}.",
+                            object.name,
+                            object_field.name,
+                            object_arg.name,
+                            interface.name,
+                            interface_field.name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_union(&self, union: &TypeInfo<'a>, errors: &mut Vec<ValidationError>) {
+        if union.members.is_empty() {
+            errors.push(ValidationError::new(
+                union.position,
+                format!(
+                    "Union type {} must define one or more member types.",
+                    union.name
+                ),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for member in &union.members {
+            if !seen.insert(member) {
+                errors.push(ValidationError::new(
+                    union.position,
+                    format!(
+                        "Union type {} can only include type {} once.",
+                        union.name, member
+                    ),
+                ));
+                continue;
+            }
+
+            match self.type_(member) {
+                Some(type_) if type_.kind == TypeKind::Object => {}
+                Some(_) => errors.push(ValidationError::new(
+                    union.position,
+                    format!(
+                        "Union type {} can only include Object types, it cannot include {}.",
+                        union.name, member
+                    ),
+                )),
+                None => errors.push(ValidationError::new(
+                    union.position,
+                    format!("Unknown type \"{}\".", member),
+                )),
+            }
+        }
+    }
+
+    fn validate_input_object(&self, input: &TypeInfo<'a>, errors: &mut Vec<ValidationError>) {
+        if input.input_fields.is_empty() {
+            errors.push(ValidationError::new(
+                input.position,
+                format!(
+                    "Input Object type {} must define one or more fields.",
+                    input.name
+                ),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for field in &input.input_fields {
+            check_name(&field.name, field.position, errors);
+
+            if !seen.insert(field.name.as_str()) {
+                errors.push(ValidationError::new(
+                    field.position,
+                    format!(
+                        "Input field \"{}.{}\" can only be defined once.",
+                        input.name, field.name
+                    ),
+                ));
+            }
+
+            if let Some(field_type) =
+                self.check_type_reference(&field.value_type, field.position, errors)
+            {
+                if !field_type.kind.is_input() {
+                    errors.push(ValidationError::new(
+                        field.position,
+                        format!(
+                            "The type of {}.{} must be Input Type but got: {}.",
+                            input.name, field.name, field.value_type
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn validate_enum(enum_: &TypeInfo, errors: &mut Vec<ValidationError>) {
+    if enum_.values.is_empty() {
+        errors.push(ValidationError::new(
+            enum_.position,
+            format!("Enum type {} must define one or more values.", enum_.name),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for value in &enum_.values {
+        check_name(&value.name, value.position, errors);
+
+        if !seen.insert(value.name.as_str()) {
+            errors.push(ValidationError::new(
+                value.position,
+                format!(
+                    "Enum value \"{}.{}\" can only be defined once.",
+                    enum_.name, value.name
+                ),
+            ));
+        }
+
+        if value.name == "true" || value.name == "false" || value.name == "null" {
+            errors.push(ValidationError::new(
+                value.position,
+                format!(
+                    "Enum type {} cannot include value: {}.",
+                    enum_.name, value.name
+                ),
+            ));
+        }
+    }
+}
+
+fn check_name(name: &str, position: Pos, errors: &mut Vec<ValidationError>) {
+    if name.starts_with("__") {
+        errors.push(ValidationError::new(
+            position,
+            format!(
+                "Name \"{}\" must not begin with \"__\", which is reserved by GraphQL introspection.",
+                name
+            ),
+        ));
+    }
+}
+
+fn extension_info(ext: &TypeExtension) -> (&str, TypeKind, Pos) {
+    match ext {
+        TypeExtension::Scalar(x) => (&x.name, TypeKind::Scalar, x.position),
+        TypeExtension::Object(x) => (&x.name, TypeKind::Object, x.position),
+        TypeExtension::Interface(x) => (&x.name, TypeKind::Interface, x.position),
+        TypeExtension::Union(x) => (&x.name, TypeKind::Union, x.position),
+        TypeExtension::Enum(x) => (&x.name, TypeKind::Enum, x.position),
+        TypeExtension::InputObject(x) => (&x.name, TypeKind::InputObject, x.position),
+    }
+}
+
+pub fn named_type(type_: &Type) -> &str {
+    match type_ {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+pub fn is_non_null(type_: &Type) -> bool {
+    match type_ {
+        Type::NonNullType(_) => true,
+        Type::NamedType(_) | Type::ListType(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn errors(schema: &str) -> Vec<String> {
+        let doc = parse_schema(schema.trim()).unwrap();
+        validate(&doc)
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_schema() {
+        let schema = "
+schema { query: Root }
+interface Node { id: ID! }
+type Root { node(id: ID!): Node search(filter: Filter): [SearchResult!]! }
+type User implements Node { id: ID! name: String }
+type Team implements Node { id: ID! users(first: Int): [User] }
+union SearchResult = User | Team
+input Filter { query: String! kind: Kind = USER }
+enum Kind { USER TEAM }
+extend type User { team: Team }
+        ";
+
+        assert_eq!(errors(schema), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_undefined_types() {
+        let schema = "
+type Query { user(filter: Filter): User }
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec![
+                "1:14: Unknown type \"User\".",
+                "1:19: Unknown type \"Filter\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let schema = "
+type Query { a: Int a: String b(x: Int, x: Int): Int }
+type Query { c: Int }
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec![
+                "1:21: Field \"Query.a\" can only be defined once.",
+                "1:41: Argument \"Query.b(x:)\" can only be defined once.",
+                "2:1: There can be only one type named \"Query\".",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interface_not_implemented() {
+        let schema = "
+type Query { node: Node }
+interface Node { id: ID! name(upper: Boolean): String }
+type User implements Node { id: String name: String }
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec![
+                "3:29: Interface field Node.id expects type ID! but User.id is type String.",
+                "3:40: Interface field argument Node.name(upper:) expected but User.name does not provide it.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interface_field_missing() {
+        let schema = "
+type Query { node: Node }
+interface Node { id: ID! }
+type User implements Node & Query { name: String }
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec![
+                "3:1: Interface field Node.id expected but User does not provide it.",
+                "3:1: Type User must only implement Interface types, it cannot implement Query.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_union_members() {
+        let schema = "
+type Query { result: Result }
+scalar Date
+union Result = Query | Date
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec!["3:1: Union type Result can only include Object types, it cannot include Date."]
+        );
+    }
+
+    #[test]
+    fn test_input_and_output_types() {
+        let schema = "
+type Query { user(filter: Query): Filter }
+input Filter { query: Query }
+        ";
+
+        assert_eq!(
+            errors(schema),
+            vec![
+                "1:14: The type of Query.user must be Output Type but got: Filter.",
+                "1:19: The type of Query.user(filter:) must be Input Type but got: Query.",
+                "2:16: The type of Filter.query must be Input Type but got: Query.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_root_query_type() {
+        assert_eq!(
+            errors("type User { id: ID }"),
+            vec!["1:1: Query root type must be provided."]
+        );
+
+        assert_eq!(
+            errors("schema { query: Root } type Query { id: ID }"),
+            vec!["1:1: Unknown type \"Root\"."]
+        );
+
+        assert_eq!(
+            errors("schema { query: Root } input Root { id: ID }"),
+            vec!["1:1: Query root type must be Object type, it cannot be Root."]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Running the binary, for checking what ends up on stdout.
+
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// An empty directory for the files of the test `name`.
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gqltools-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &PathBuf, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gqltools"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn validate_json_output() {
+    let dir = dir("validate-json");
+    fs::write(
+        dir.join("schema.graphql"),
+        "type Query { a(x: Float): Int }",
+    )
+    .unwrap();
+    fs::write(dir.join("query.graphql"), "query Q { a(x: 1.5) b }").unwrap();
+
+    let output = run(
+        &dir,
+        &[
+            "validate",
+            "--query",
+            "query.graphql",
+            "--schema",
+            "schema.graphql",
+            "--output-format",
+            "json",
+        ],
+        "",
+    );
+    let problems: Value = serde_json::from_str(&stdout(&output)).unwrap();
+
+    assert_eq!(
+        problems[0]["message"],
+        "Cannot query field \"b\" on type \"Query\"."
+    );
+    assert_eq!(problems.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn format_stdin() {
+    let dir = dir("format-stdin");
+
+    let output = run(&dir, &["format", "-"], "query Q { a(x: 1.5) }");
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "query Q {\n  a(x: 1.5)\n}\n");
+}
+
+#[test]
+fn minify_output() {
+    let dir = dir("minify");
+    fs::write(dir.join("query.graphql"), "query Q { a(x: 1.5, y: [1.0]) }").unwrap();
+
+    let output = run(&dir, &["format", "--minify", "query.graphql"], "");
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "query Q{a(x:1.5 y:[1.0])}\n");
+}
+
+#[test]
+fn persist_stdout() {
+    let dir = dir("persist");
+    fs::write(dir.join("query.graphql"), "query Q { a(x: 1.5) }").unwrap();
+
+    let output = run(&dir, &["persist", "query.graphql"], "");
+    let manifest: Value = serde_json::from_str(&stdout(&output)).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(manifest["operations"][0]["document"], "query Q{a(x:1.5)}");
+}
+
+#[test]
+fn validate_skips_directories_matching_the_pattern() {
+    let dir = dir("validate-dirs");
+    fs::create_dir(dir.join("dir.graphql")).unwrap();
+    fs::write(dir.join("schema.graphql"), "type Query { a: Int }").unwrap();
+    fs::write(dir.join("query.graphql"), "{ a }").unwrap();
+
+    let output = run(
+        &dir,
+        &[
+            "validate",
+            "--query",
+            "*.graphql",
+            "--schema",
+            "schema.graphql",
+        ],
+        "",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "OK: query.graphql\n");
+}
+
+#[test]
+fn format_prints_a_single_file() {
+    let dir = dir("format-files");
+    fs::write(dir.join("a.graphql"), "{ a }").unwrap();
+    fs::write(dir.join("b.graphql"), "{ b }").unwrap();
+
+    let output = run(&dir, &["format", "a.graphql", "b.graphql"], "");
+
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--write or --check"));
+}
+
+#[test]
+fn canonical_hash_of_a_single_file() {
+    let dir = dir("hash-canonical");
+    fs::write(dir.join("a.graphql"), "{ b a }").unwrap();
+    fs::write(dir.join("b.graphql"), "{ b }").unwrap();
+
+    let single = run(&dir, &["hash", "--canonical", "a.graphql"], "");
+    let several = run(&dir, &["hash", "--canonical", "a.graphql", "b.graphql"], "");
+
+    assert_eq!(stdout(&single), "{a b}\n");
+    assert!(!several.status.success());
+    assert_eq!(stdout(&several), "");
+}